@@ -0,0 +1,131 @@
+//! Replays are not distributed with the crate, so point `SLP_BENCH_DIR` at a folder of replays
+//! (around 100 is plenty) before running `cargo bench`. The replay benchmarks are skipped if it is unset.
+//!
+//! `combo_start` does not need replays, it is measured through `find_combo_start` on a game built in code.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use slp_combo_finder::Config;
+use slp_parser::{ActionState, Frame, StandardActionState};
+use std::path::PathBuf;
+
+/// Only as many replays as this are scanned by `target_path_100`.
+const FILE_COUNT: usize = 100;
+
+fn bench_dir() -> Option<PathBuf> {
+    let dir = std::env::var_os("SLP_BENCH_DIR").map(PathBuf::from);
+    if dir.is_none() { eprintln!("SLP_BENCH_DIR is not set, skipping benchmark"); }
+    dir
+}
+
+/// The first `FILE_COUNT` replays directly in `dir`, by path.
+fn bench_files(dir: &PathBuf) -> Vec<PathBuf> {
+    let mut files = std::fs::read_dir(dir)
+        .expect("SLP_BENCH_DIR is not a folder")
+        .filter_map(|e| Some(e.ok()?.path()))
+        .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("slp" | "slpz")))
+        .collect::<Vec<_>>();
+    files.sort();
+    files.truncate(FILE_COUNT);
+    files
+}
+
+fn target_path(c: &mut Criterion) {
+    let Some(dir) = bench_dir() else { return };
+    let config = Config::DEFAULT;
+
+    let (_, stats) = slp_combo_finder::target_path_with_stats(&config, &dir, None)
+        .expect("SLP_BENCH_DIR does not exist");
+
+    let mut group = c.benchmark_group("target_path");
+    group.sample_size(10);
+
+    group.throughput(Throughput::Elements(stats.files_discovered as u64));
+    group.bench_function("files", |b| b.iter(|| slp_combo_finder::target_path(&config, &dir, None)));
+
+    group.throughput(Throughput::Elements(stats.combos_found as u64));
+    group.bench_function("combos", |b| b.iter(|| slp_combo_finder::target_path(&config, &dir, None)));
+
+    group.finish();
+}
+
+fn target_path_100(c: &mut Criterion) {
+    let Some(dir) = bench_dir() else { return };
+    let config = Config::DEFAULT;
+    let files = bench_files(&dir);
+
+    let (_, stats) = slp_combo_finder::target_paths_with_stats(&config, &files, None).unwrap();
+
+    let mut group = c.benchmark_group("target_path_100");
+    group.sample_size(10);
+
+    group.throughput(Throughput::Elements(files.len() as u64));
+    group.bench_function("files", |b| b.iter(|| slp_combo_finder::target_paths(&config, &files, None)));
+
+    group.throughput(Throughput::Elements(stats.combos_found as u64));
+    group.bench_function("combos", |b| b.iter(|| slp_combo_finder::target_paths(&config, &files, None)));
+
+    group.finish();
+}
+
+/// Number of stocks taken in the game built by `synthetic_game`.
+const SYNTHETIC_KILLS: usize = 16;
+
+/// A game built in code so `combo_start` is always measured: each stock, both players stand for a second,
+/// then the attacker lands 5 back airs that keep the defender in hitstun until they die.
+///
+/// Returns the attacker's and defender's frames, and the frames the defender died on.
+fn synthetic_game() -> (Vec<Frame>, Vec<Frame>, Vec<usize>) {
+    let frame = |state, percent| Frame { state: ActionState::Standard(state), percent, ..Frame::default() };
+
+    let (mut atk, mut def, mut deaths) = (Vec::new(), Vec::new(), Vec::new());
+    for _ in 0..SYNTHETIC_KILLS {
+        for _ in 0..60 {
+            atk.push(frame(StandardActionState::Wait, 0.0));
+            def.push(frame(StandardActionState::Wait, 0.0));
+        }
+        for hit in 1..=5 {
+            for f in 0..12 {
+                let attacking = if f < 6 { StandardActionState::AttackAirB } else { StandardActionState::Wait };
+                atk.push(frame(attacking, 0.0));
+                def.push(frame(StandardActionState::DamageFlyHi, hit as f32 * 14.0));
+            }
+        }
+        deaths.push(def.len());
+        for _ in 0..30 {
+            atk.push(frame(StandardActionState::Wait, 0.0));
+            def.push(frame(StandardActionState::DeadDown, 0.0));
+        }
+    }
+    (atk, def, deaths)
+}
+
+fn combo_start(c: &mut Criterion) {
+    let config = Config::DEFAULT;
+    let (atk, def, deaths) = synthetic_game();
+
+    let mut group = c.benchmark_group("combo_start");
+    group.throughput(Throughput::Elements(deaths.len() as u64));
+    group.bench_function("combos", |b| b.iter(|| {
+        deaths.iter()
+            .filter_map(|&f| slp_combo_finder::find_combo_start(&atk[..f], &def[..f], &config))
+            .count()
+    }));
+    group.finish();
+}
+
+fn single_thread(c: &mut Criterion) {
+    let Some(dir) = bench_dir() else { return };
+    let config = Config { threads: Some(1), ..Config::DEFAULT };
+
+    let (_, stats) = slp_combo_finder::target_path_with_stats(&config, &dir, None)
+        .expect("SLP_BENCH_DIR does not exist");
+
+    let mut group = c.benchmark_group("single_thread");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(stats.files_discovered as u64));
+    group.bench_function("files", |b| b.iter(|| slp_combo_finder::target_path(&config, &dir, None)));
+    group.finish();
+}
+
+criterion_group!(benches, target_path, target_path_100, combo_start, single_thread);
+criterion_main!(benches);
@@ -0,0 +1,53 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slp_combo_finder::{ComboBreakerMode, Config};
+use slp_parser::{ActionState, Frame, StandardActionState::{self, *}};
+
+/// States from every broad state `combo_start` treats differently.
+const STATES: &[StandardActionState] = &[
+    Wait, Dash, Fall, Landing, AttackAirB, AttackAirN, AttackS4S, AttackLw4, Catch, CatchWait, ThrowF, ThrowLw,
+    DamageN1, DamageHi3, DamageFlyHi, DamageFall, DownWaitU, Guard, GuardSetOff, EscapeAir, LandingFallSpecial,
+    DeadDown,
+];
+
+fn frame(state: u8, anim_frame: u8, percent: f32) -> Frame {
+    Frame {
+        state: ActionState::Standard(STATES[state as usize % STATES.len()]),
+        anim_frame: anim_frame as f32,
+        percent,
+        ..Frame::default()
+    }
+}
+
+// Any frames and config must give a combo start or none, never panic. Strictness is not limited to 0 to 1,
+// so the thresholds derived from it can be 0.
+fuzz_target!(|input: (f32, u8, Vec<(u8, u8, u8, u8, u8)>)| {
+    let (strictness, mode, frames) = input;
+
+    let config = Config {
+        strictness,
+        combo_breaker: match mode % 3 {
+            0 => None,
+            1 => Some(ComboBreakerMode::Reject),
+            _ => Some(ComboBreakerMode::Truncate),
+        },
+        max_hit_gap_frames: Some(mode as usize),
+        min_connected_hits: Some(mode as usize % 8),
+        max_grab_ratio: Some(0.5),
+        ..Config::DEFAULT
+    };
+
+    let mut percent = 0.0;
+    let mut atk = Vec::with_capacity(frames.len());
+    let mut def = Vec::with_capacity(frames.len());
+    for (atk_state, atk_anim, def_state, def_anim, damage) in frames {
+        percent += damage as f32;
+        atk.push(frame(atk_state, atk_anim, 0.0));
+        def.push(frame(def_state, def_anim, percent));
+    }
+
+    if let Some(start) = slp_combo_finder::find_combo_start(&atk, &def, &config) {
+        assert!(start < def.len());
+    }
+});
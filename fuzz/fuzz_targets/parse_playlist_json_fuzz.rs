@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Any string must either parse or return an error, never panic.
+fuzz_target!(|data: &str| {
+    let _ = slp_combo_finder::parse_playlist_json(data);
+});
@@ -0,0 +1,29 @@
+#ifndef COMBO_FINDER_H
+#define COMBO_FINDER_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Finds combos in every replay under `path` (a folder or tree of folders)
+ * and returns the resulting Dolphin playlist json.
+ *
+ * `config_json` is a json object using the field names of the Rust `Config`
+ * struct, e.g. {"strictness": 0.6, "player_code": "ABCD#123"}.
+ * Missing fields take their default values.
+ *
+ * Returns NULL if an argument is NULL or not utf8, if the config is invalid,
+ * or if the path does not exist.
+ * The returned string must be freed with combo_finder_free_string.
+ */
+char *combo_finder_run(const char *config_json, const char *path);
+
+/* Frees a string returned by combo_finder_run. Does nothing if ptr is NULL. */
+void combo_finder_free_string(char *ptr);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* COMBO_FINDER_H */
@@ -0,0 +1,60 @@
+//! Async scanning for embedding in a tokio runtime, enabled with the `tokio` feature.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use crate::{Combo, Config, ProgressEvent, TargetPathError};
+
+/// Async version of `target_path`.
+///
+/// `slp_parser` is synchronous, so the directory walk and each file's scan run on tokio's blocking pool.
+/// Combos are sorted by path, then start frame, and at most `Config::max_combos` are returned, as with `target_path`.
+/// Unlike `target_path`, `Config::checkpoint` is ignored: every file is scanned and nothing is recorded.
+pub async fn target_path_async(
+    config: Config,
+    path: PathBuf,
+    tx: Option<tokio::sync::mpsc::Sender<ProgressEvent>>,
+) -> Result<Vec<Combo>, TargetPathError> {
+    crate::check_target(&config, &path)?;
+
+    let walk_config = config.clone();
+    let targets = tokio::task::spawn_blocking(move || crate::walk(&walk_config, &path).targets)
+        .await.expect("Directory walk panicked");
+
+    if targets.is_empty() {
+        log::warn!("No replay files found");
+        if config.strict { return Err(TargetPathError::NoFilesFound) }
+        if let Some(ref tx) = tx { let _ = tx.send(ProgressEvent::NoFilesFound).await; }
+    }
+
+    if let Some(ref tx) = tx { let _ = tx.send(ProgressEvent::TargetCount(targets.len())).await; }
+
+    let config = Arc::new(config);
+    let mut tasks = tokio::task::JoinSet::new();
+    for t in targets {
+        let config = config.clone();
+        tasks.spawn_blocking(move || crate::find_combos_in_file(&config, &t));
+    }
+
+    let mut combo_vec = Vec::new();
+    while let Some(file_combos) = tasks.join_next().await {
+        let file_combos = file_combos.expect("Scanning file panicked");
+
+        if let Some(ref tx) = tx {
+            for c in file_combos.iter() { let _ = tx.send(ProgressEvent::ComboFound(c.clone())).await; }
+            let _ = tx.send(ProgressEvent::FileScanned).await;
+        }
+
+        combo_vec.extend(file_combos);
+
+        // files not yet started are cancelled, ones already running finish on the blocking pool
+        if config.max_combos.is_some_and(|max| combo_vec.len() >= max) {
+            tasks.abort_all();
+            break;
+        }
+    }
+
+    combo_vec.sort_by(|a, b| a.path.cmp(&b.path).then(a.start.cmp(&b.start)));
+    if let Some(max) = config.max_combos { combo_vec.truncate(max); }
+
+    Ok(combo_vec)
+}
@@ -0,0 +1,151 @@
+//! A chainable way to build a `Config`, so that embedding code keeps compiling as fields are added.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use crate::{ComboBreakerMode, ComboEnder, ComboOpener, Config, MatchType};
+
+/// Builds a `Config`, starting from `Config::DEFAULT`.
+///
+/// ```ignore
+/// let config = Config::builder()
+///     .strictness(0.7)
+///     .player_character(Character::Fox)
+///     .lead_in(45)
+///     .build()?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ConfigBuildError {
+    /// Strictness must be between 0 and 1.
+    InvalidStrictness(f32),
+}
+
+impl std::fmt::Display for ConfigBuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigBuildError::InvalidStrictness(s) => write!(f, "Strictness must be between 0 and 1, not {}.", s),
+        }
+    }
+}
+
+/// Setters that store their argument as is.
+macro_rules! set {
+    ($($field:ident: $ty:ty),* $(,)?) => {
+        $(
+            #[doc = concat!("Sets `Config::", stringify!($field), "`.")]
+            pub fn $field(mut self, $field: $ty) -> Self {
+                self.config.$field = $field;
+                self
+            }
+        )*
+    };
+}
+
+/// Setters for optional fields, which take the value to wrap in `Some`.
+macro_rules! set_some {
+    ($($field:ident: $ty:ty),* $(,)?) => {
+        $(
+            #[doc = concat!("Sets `Config::", stringify!($field), "`.")]
+            pub fn $field(mut self, $field: impl Into<$ty>) -> Self {
+                self.config.$field = Some($field.into());
+                self
+            }
+        )*
+    };
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        ConfigBuilder { config: Config::DEFAULT }
+    }
+
+    set! {
+        lead_in: usize,
+        lead_out: usize,
+        strictness: f32,
+        player_won_only: bool,
+        follow_symlinks: bool,
+        strict: bool,
+        exclude_dirs: Vec<String>,
+        attacker_last_stock: bool,
+        flawless: bool,
+        flawless_allow_shield_hits: bool,
+        true_combos_only: bool,
+        tumble_breaks_true_combo: bool,
+        detect_tech: bool,
+        checkpoint_interval: usize,
+    }
+
+    set_some! {
+        lead_in_override: HashMap<slp_parser::Character, usize>,
+        lead_out_override: HashMap<slp_parser::Character, usize>,
+        player_character: slp_parser::Character,
+        player_code: String,
+        player_name: String,
+        opponent_character: slp_parser::Character,
+        opponent_code: String,
+        opponent_name: String,
+        player_characters: Vec<slp_parser::Character>,
+        opponent_characters: Vec<slp_parser::Character>,
+        either_player_code: String,
+        combo_breaker: ComboBreakerMode,
+        threads: usize,
+        match_type: MatchType,
+        min_version: (u8, u8, u8),
+        max_defender_stocks: u8,
+        max_attacker_stocks: u8,
+        min_attacker_percent: f32,
+        max_attacker_damage: f32,
+        max_depth: usize,
+        starter_move: Vec<slp_parser::ActionState>,
+        extensions: Vec<String>,
+        out_of_shield_frames: usize,
+        opener: ComboOpener,
+        ender: ComboEnder,
+        min_distinct_moves: usize,
+        min_attacker_attacks: usize,
+        min_connected_hits: usize,
+        max_combos: usize,
+        max_hit_gap_frames: usize,
+        min_total_game_damage: f32,
+        min_game_duration_frames: usize,
+        max_game_duration_frames: usize,
+        max_grab_ratio: f32,
+        checkpoint: PathBuf,
+    }
+
+    /// Adds a single character's entry to `Config::lead_in_override`.
+    pub fn lead_in_for(mut self, character: slp_parser::Character, frames: usize) -> Self {
+        self.config.lead_in_override.get_or_insert_with(HashMap::new).insert(character, frames);
+        self
+    }
+
+    /// Adds a single character's entry to `Config::lead_out_override`.
+    pub fn lead_out_for(mut self, character: slp_parser::Character, frames: usize) -> Self {
+        self.config.lead_out_override.get_or_insert_with(HashMap::new).insert(character, frames);
+        self
+    }
+
+    pub fn build(self) -> Result<Config, ConfigBuildError> {
+        let strictness = self.config.strictness;
+        if !(0.0..=1.0).contains(&strictness) { return Err(ConfigBuildError::InvalidStrictness(strictness)) }
+
+        Ok(self.config)
+    }
+}
+
+impl Default for ConfigBuilder {
+    fn default() -> Self {
+        ConfigBuilder::new()
+    }
+}
+
+impl Config {
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::new()
+    }
+}
@@ -0,0 +1,101 @@
+//! Checkpoint files, so that an interrupted scan can resume where it left off.
+//!
+//! A checkpoint is a json object per line, one per scanned file:
+//! `{"path":"...","combos":[{"start":123,"end":456,"gameEnding":false}]}`.
+//! Lines are only ever appended, so a scan killed partway through a write loses at most that line.
+//! Combo metadata is not stored, so resumed combos have `metadata: None`.
+//! Paths that are not valid UTF-8 are not recorded, so those files are scanned again on resume.
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use crate::{Combo, FrameIndex};
+
+/// The files already scanned and the combos found in them.
+pub(crate) fn load(checkpoint_path: &Path) -> (HashSet<PathBuf>, Vec<Combo>) {
+    let mut processed = HashSet::new();
+    let mut combos = Vec::new();
+
+    // a missing checkpoint is a fresh scan
+    let file = match std::fs::read_to_string(checkpoint_path) {
+        Ok(f) => f,
+        Err(_) => return (processed, combos),
+    };
+
+    for line in file.lines() {
+        // skip lines cut off by an interrupted write
+        let entry = match json::parse(line) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        let path = match entry["path"].as_str() {
+            Some(p) => PathBuf::from(p),
+            None => continue,
+        };
+
+        for c in entry["combos"].members() {
+            let (Some(start), Some(end)) = (c["start"].as_usize(), c["end"].as_usize()) else { continue };
+            combos.push(Combo {
+                path: path.clone(),
+                start: FrameIndex(start),
+                end: FrameIndex(end),
+                game_ending: c["gameEnding"].as_bool().unwrap_or(false),
+                metadata: None,
+            });
+        }
+
+        processed.insert(path);
+    }
+
+    (processed, combos)
+}
+
+/// Buffers scanned files and appends them to the checkpoint every `interval` files.
+pub(crate) struct Checkpoint {
+    path: PathBuf,
+    interval: usize,
+    pending: String,
+    pending_files: usize,
+    pub(crate) failed: bool,
+}
+
+impl Checkpoint {
+    pub(crate) fn new(path: PathBuf, interval: usize) -> Self {
+        Checkpoint { path, interval: interval.max(1), pending: String::new(), pending_files: 0, failed: false }
+    }
+
+    pub(crate) fn record(&mut self, path: &Path, combos: &[Combo]) {
+        // a lossy path would not match on resume, so the file would be scanned again and its combos duplicated
+        let Some(path) = path.to_str() else {
+            log::warn!("Not recording {:?} in the checkpoint, its path is not valid UTF-8", path);
+            return;
+        };
+
+        let combos = combos.iter()
+            .map(|c| json::object!{ start: c.start.0, end: c.end.0, gameEnding: c.game_ending })
+            .collect::<Vec<_>>();
+        let entry = json::object!{ path: path, combos: combos };
+
+        self.pending.push_str(&entry.dump());
+        self.pending.push('\n');
+        self.pending_files += 1;
+
+        if self.pending_files >= self.interval { self.flush(); }
+    }
+
+    pub(crate) fn flush(&mut self) {
+        if self.pending.is_empty() { return }
+
+        let written = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut f| f.write_all(self.pending.as_bytes()));
+
+        // keep scanning without a checkpoint rather than abort the scan
+        if written.is_err() { self.failed = true; }
+
+        self.pending.clear();
+        self.pending_files = 0;
+    }
+}
@@ -0,0 +1,154 @@
+//! Writers for formats other than Dolphin playlists.
+//! Frames use the same numbering as playlists, so entries can be matched back up.
+
+use std::fmt::Write as _;
+use std::path::Path;
+use crate::Combo;
+
+/// Melee runs at 60 frames per second.
+const GAME_FPS: f32 = 60.0;
+
+/// The json object written for each combo by `write_metadata_json` and `write_jsonl`.
+pub(crate) fn combo_json(c: &Combo) -> json::JsonValue {
+    fn non_empty(s: &str) -> json::JsonValue {
+        if s.is_empty() { json::JsonValue::Null } else { s.into() }
+    }
+
+    let mut entry = json::object!{
+        path: c.path.to_string_lossy().into_owned(),
+        startFrame: c.start.to_slippi_frame(),
+        endFrame: c.end.to_slippi_frame(),
+        gameEnding: c.game_ending,
+    };
+
+    if let Some(ref m) = c.metadata {
+        entry["attackerCharacter"] = format!("{:?}", m.attacker_character).into();
+        entry["attackerName"] = non_empty(&m.attacker_name);
+        entry["attackerCode"] = non_empty(&m.attacker_code);
+        entry["defenderCharacter"] = format!("{:?}", m.defender_character).into();
+        entry["defenderName"] = non_empty(&m.defender_name);
+        entry["defenderCode"] = non_empty(&m.defender_code);
+        entry["attackerPort"] = m.attacker_port.into();
+        entry["defenderPort"] = m.defender_port.into();
+        entry["rawStartFrame"] = m.raw_start.to_slippi_frame().into();
+        entry["rawEndFrame"] = m.raw_end.to_slippi_frame().into();
+        entry["startPercent"] = m.start_percent.into();
+        entry["endPercent"] = m.end_percent.into();
+        entry["damage"] = m.damage().into();
+        entry["openerMove"] = m.opener_move.map(|s| format!("{:?}", s)).into();
+        entry["opener"] = m.opener.map(|o| o.name()).into();
+        entry["killMoveCategory"] = m.kill_move_category.map(|k| k.name()).into();
+        entry["ender"] = m.ender.map(|e| e.name()).into();
+        entry["killDirection"] = m.kill_direction.map(|d| d.name()).into();
+        entry["grabRatio"] = m.grab_ratio.into();
+        entry["grabCount"] = m.grab_count.into();
+        entry["hitCount"] = m.hit_count.into();
+        entry["tech"] = m.tech.map(|t| t.name()).into();
+        entry["trueCombo"] = m.is_true_combo.into();
+    }
+
+    entry
+}
+
+/// One compact json object per line, with the same fields as `write_metadata_json`.
+pub fn write_jsonl(combos: &[Combo], out_path: &Path) -> std::io::Result<()> {
+    let mut out = String::new();
+    for c in combos {
+        out.push_str(&combo_json(c).dump());
+        out.push('\n');
+    }
+    std::fs::write(out_path, out)
+}
+
+/// A header row, then one row per combo. Metadata columns are empty for combos without metadata.
+pub fn write_csv(combos: &[Combo], out_path: &Path) -> std::io::Result<()> {
+    fn field(out: &mut String, s: &str) {
+        if s.contains([',', '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&s.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(s);
+        }
+    }
+
+    let mut out = String::from(
+        "path,start_frame,end_frame,game_ending,attacker_character,attacker_name,attacker_code,\
+        defender_character,defender_name,defender_code,start_percent,end_percent,damage\n"
+    );
+
+    for c in combos {
+        field(&mut out, &c.path.to_string_lossy());
+        write!(out, ",{},{},{}", c.start.to_slippi_frame(), c.end.to_slippi_frame(), c.game_ending).unwrap();
+
+        match c.metadata {
+            Some(ref m) => {
+                write!(out, ",{:?},", m.attacker_character).unwrap();
+                field(&mut out, &m.attacker_name);
+                out.push(',');
+                field(&mut out, &m.attacker_code);
+                write!(out, ",{:?},", m.defender_character).unwrap();
+                field(&mut out, &m.defender_name);
+                out.push(',');
+                field(&mut out, &m.defender_code);
+                write!(out, ",{},{},{}", m.start_percent, m.end_percent, m.damage()).unwrap();
+            }
+            None => out.push_str(",,,,,,,,,"),
+        }
+
+        out.push('\n');
+    }
+
+    std::fs::write(out_path, out)
+}
+
+/// Subtitles for a video of every combo played back to back.
+///
+/// Each clip is assumed to play at `fps` game frames per second, with `gap_seconds` between clips.
+pub fn write_srt(combos: &[Combo], out_path: &Path, fps: f32, gap_seconds: f32) -> std::io::Result<()> {
+    fn srt_time(seconds: f32) -> String {
+        let ms = (seconds.max(0.0) * 1000.0).round() as u64;
+        format!("{:02}:{:02}:{:02},{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000)
+    }
+
+    let mut out = String::new();
+    let mut time = 0.0;
+    for (i, c) in combos.iter().enumerate() {
+        let duration = c.end.0.saturating_sub(c.start.0) as f32 / fps;
+        write!(out, "{}\n{} --> {}\n{}\n\n", i + 1, srt_time(time), srt_time(time + duration), caption(c)).unwrap();
+        time += duration + gap_seconds;
+    }
+
+    std::fs::write(out_path, out)
+}
+
+/// Chapter markers (`m:ss caption`) for a video of every combo played back to back at 60fps,
+/// with `gap_seconds` between clips.
+pub fn write_timestamps(combos: &[Combo], out_path: &Path, gap_seconds: f32) -> std::io::Result<()> {
+    let mut out = String::new();
+    let mut time = 0.0;
+    for c in combos {
+        let seconds = time as u64;
+        writeln!(out, "{}:{:02} {}", seconds / 60, seconds % 60, caption(c)).unwrap();
+        time += c.end.0.saturating_sub(c.start.0) as f32 / GAME_FPS + gap_seconds;
+    }
+
+    std::fs::write(out_path, out)
+}
+
+/// e.g. "Fox (Mango) > Marth (Zain), 84%", or the file name for combos without metadata.
+fn caption(c: &Combo) -> String {
+    fn player(character: slp_parser::Character, name: &str) -> String {
+        if name.is_empty() { format!("{:?}", character) } else { format!("{:?} ({})", character, name) }
+    }
+
+    match c.metadata {
+        Some(ref m) => format!(
+            "{} > {}, {:.0}%",
+            player(m.attacker_character, &m.attacker_name),
+            player(m.defender_character, &m.defender_name),
+            m.damage(),
+        ),
+        None => c.path.file_name().unwrap_or(c.path.as_os_str()).to_string_lossy().into_owned(),
+    }
+}
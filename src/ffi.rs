@@ -0,0 +1,54 @@
+//! C api for calling the combo finder from other languages. The matching header is `include/combo_finder.h`.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::path::Path;
+
+/// Runs `target_path` and returns the resulting playlist json.
+///
+/// `config_json` is a json object as accepted by `Config::from_json`.
+/// Returns null if an argument is null or not utf8, if the config is invalid, or if the path does not exist.
+/// The returned string must be freed with `combo_finder_free_string`.
+///
+/// # Safety
+///
+/// `config_json` and `path` must be null or point to nul terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn combo_finder_run(config_json: *const c_char, path: *const c_char) -> *mut c_char {
+    if config_json.is_null() || path.is_null() { return std::ptr::null_mut() }
+
+    let config_json = match CStr::from_ptr(config_json).to_str() {
+        Ok(s) => s,
+        Err(_) => return std::ptr::null_mut(),
+    };
+    let path = match CStr::from_ptr(path).to_str() {
+        Ok(s) => s,
+        Err(_) => return std::ptr::null_mut(),
+    };
+
+    let config = match crate::Config::from_json(config_json) {
+        Ok(c) => c,
+        Err(_) => return std::ptr::null_mut(),
+    };
+
+    let combos = match crate::target_path(&config, Path::new(path), None) {
+        Ok(c) => c,
+        Err(_) => return std::ptr::null_mut(),
+    };
+
+    match CString::new(crate::write_playlist_to_string(&combos)) {
+        Ok(s) => s.into_raw(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Frees a string returned by `combo_finder_run`. Does nothing if `ptr` is null.
+///
+/// # Safety
+///
+/// `ptr` must be null or have been returned by `combo_finder_run`, and must not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn combo_finder_free_string(ptr: *mut c_char) {
+    if ptr.is_null() { return }
+    drop(CString::from_raw(ptr));
+}
@@ -0,0 +1,93 @@
+//! Reads fields of a replay's Game Start event that `slp_parser::read_info` does not expose,
+//! straight from the start of the file so it stays as cheap as `read_info`.
+//!
+//! Only uncompressed `.slp` files can be read this way; `.slpz` files return `None`.
+//! `check_file` also looks at the raw element's header, to explain why slp_parser failed to read a file.
+
+use std::io::Read;
+use std::path::Path;
+
+/// `{U\x03raw[$U#l` followed by the big endian length of the raw element.
+const RAW_HEADER: &[u8] = b"{U\x03raw[$U#l";
+const RAW_START: usize = RAW_HEADER.len() + 4;
+
+const EVENT_PAYLOADS: u8 = 0x35;
+const GAME_START: u8 = 0x36;
+
+/// Offsets within the Game Start event, counting its command byte.
+const VERSION_OFFSET: usize = 0x1;
+const MATCH_ID_OFFSET: usize = 0x2BE;
+const MATCH_ID_LEN: usize = 51;
+/// First replay version with a match id.
+const MATCH_ID_VERSION: (u8, u8, u8) = (3, 14, 0);
+
+/// Why an uncompressed replay could not be parsed, if it can be told from the raw element's header.
+pub(crate) enum FileDamage {
+    /// The file does not start like a replay.
+    HeaderInvalid,
+    /// The raw element is cut short, or its length was never written because the game did not finish recording.
+    Truncated,
+}
+
+pub(crate) fn check_file(path: &Path) -> Option<FileDamage> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let file_len = file.metadata().ok()?.len();
+
+    let mut header = [0u8; RAW_START];
+    if file.read_exact(&mut header).is_err() || !header.starts_with(RAW_HEADER) {
+        return Some(FileDamage::HeaderInvalid);
+    }
+
+    // the length is 0 while the game is still being recorded
+    let raw_len = u32::from_be_bytes(header[RAW_HEADER.len()..RAW_START].try_into().unwrap());
+    if raw_len == 0 || RAW_START as u64 + raw_len as u64 > file_len {
+        return Some(FileDamage::Truncated);
+    }
+
+    None
+}
+
+pub(crate) struct GameStart {
+    pub version: (u8, u8, u8),
+    /// e.g. `mode.ranked-2023-07-01T18:37:44.88-0`. `None` for offline games and replays before 3.14.0.
+    pub match_id: Option<String>,
+}
+
+pub(crate) fn read_game_start(path: &Path) -> Option<GameStart> {
+    let mut file = std::fs::File::open(path).ok()?;
+
+    let mut header = [0u8; RAW_START + 2];
+    file.read_exact(&mut header).ok()?;
+    if !header.starts_with(RAW_HEADER) || header[RAW_START] != EVENT_PAYLOADS { return None }
+
+    // the event payloads event lists the size of every other event, game start included
+    // the size counts itself but not the command byte
+    let payloads_size = (header[RAW_START + 1] as usize).checked_sub(1)?;
+    let mut payloads = vec![0u8; payloads_size];
+    file.read_exact(&mut payloads).ok()?;
+
+    let game_start_size = payloads.chunks_exact(3)
+        .find(|p| p[0] == GAME_START)
+        .map(|p| u16::from_be_bytes([p[1], p[2]]) as usize)?;
+
+    // +1 for the command byte, which the sizes do not count
+    let mut game_start = vec![0u8; game_start_size + 1];
+    file.read_exact(&mut game_start).ok()?;
+    if game_start[0] != GAME_START || game_start.len() < VERSION_OFFSET + 3 { return None }
+
+    let version = (
+        game_start[VERSION_OFFSET],
+        game_start[VERSION_OFFSET + 1],
+        game_start[VERSION_OFFSET + 2],
+    );
+
+    let match_id = if version >= MATCH_ID_VERSION && game_start.len() >= MATCH_ID_OFFSET + MATCH_ID_LEN {
+        let bytes = &game_start[MATCH_ID_OFFSET..MATCH_ID_OFFSET + MATCH_ID_LEN];
+        let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        std::str::from_utf8(&bytes[..len]).ok().filter(|id| !id.is_empty()).map(str::to_string)
+    } else {
+        None
+    };
+
+    Some(GameStart { version, match_id })
+}
@@ -1,21 +1,567 @@
+//! # Fuzzing
+//!
+//! There are `cargo-fuzz` targets in `fuzz/`, one checking that playlist parsing never panics and one running
+//! the combo search, through `find_combo_start`, on frames built from fuzzer input. That written playlists parse
+//! back to the same frames is a proptest in `tests/playlist_roundtrip.rs`. The fuzz targets need a nightly toolchain:
+//!
+//! ```text
+//! cargo install cargo-fuzz
+//! cargo +nightly fuzz run parse_playlist_json_fuzz
+//! cargo +nightly fuzz run combo_start_fuzz
+//! ```
+
+use std::collections::HashMap;
 use std::path::{PathBuf, Path};
 
+pub mod ffi;
+
+/// `tracing::trace!` with the `tracing` feature enabled, nothing without it.
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::trace!($($arg)*);
+    };
+}
+
+mod export;
+pub use export::{write_csv, write_jsonl, write_srt, write_timestamps};
+
+mod builder;
+pub use builder::{ConfigBuilder, ConfigBuildError};
+
+mod checkpoint;
+mod game_start;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "python")]
+pub mod python;
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::{write_playlist_sqlite, read_playlist_sqlite};
+
+#[cfg(feature = "tokio")]
+mod async_scan;
+#[cfg(feature = "tokio")]
+pub use async_scan::target_path_async;
+
 #[derive(Clone, Debug)]
 pub struct Combo {
     pub path: PathBuf,
-    pub start: usize,
-    pub end: usize,
+    pub start: FrameIndex,
+    pub end: FrameIndex,
+
+    /// The kill took the defender's last stock, or the replay ended before they respawned.
+    /// Always false for combos read from a playlist.
+    pub game_ending: bool,
+
+    /// Details of the combo known when it was found. `None` for combos read from a playlist.
+    pub metadata: Option<ComboMetadata>,
+}
+
+impl Combo {
+    /// A combo from frame indices, i.e. `FrameIndex`, with no metadata.
+    pub fn new(path: impl Into<PathBuf>, start: usize, end: usize) -> Self {
+        Combo { path: path.into(), start: FrameIndex(start), end: FrameIndex(end), game_ending: false, metadata: None }
+    }
+
+    /// A combo from Slippi frame numbers, which start at `FRAME_OFFSET`, with no metadata.
+    /// `None` if either frame is before `FRAME_OFFSET`.
+    pub fn from_slippi_frames(path: impl Into<PathBuf>, start_slippi: i64, end_slippi: i64) -> Option<Self> {
+        Some(Combo {
+            path: path.into(),
+            start: FrameIndex::try_from_slippi_frame(start_slippi)?,
+            end: FrameIndex::try_from_slippi_frame(end_slippi)?,
+            game_ending: false,
+            metadata: None,
+        })
+    }
+
+    /// True if the combo does not end before it starts, and ends within a replay of `total_frames` frames.
+    /// Pass `usize::MAX` when the replay's length is not known.
+    pub fn is_valid(&self, total_frames: usize) -> bool {
+        self.start <= self.end && self.end.0 <= total_frames
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComboMetadata {
+    /// Characters at the time of the kill, so a transformed Sheik/Zelda is reported as such.
+    pub attacker_character: slp_parser::Character,
+    pub defender_character: slp_parser::Character,
+
+    /// Display names and connect codes. Empty if the replay has none (e.g. offline games).
+    pub attacker_name: String,
+    pub attacker_code: String,
+    pub defender_name: String,
+    pub defender_code: String,
+
+    /// Slippi port numbers, from 1 to 4, as shown in game.
+    pub attacker_port: usize,
+    pub defender_port: usize,
+
+    /// The combo itself, from the first hit to the kill, before `Config::lead_in` and `Config::lead_out` are
+    /// applied to give `Combo::start` and `Combo::end`.
+    pub raw_start: FrameIndex,
+    pub raw_end: FrameIndex,
+
+    /// Defender's percent just before the first hit of the combo.
+    pub start_percent: f32,
+    /// Defender's percent just before the kill.
+    pub end_percent: f32,
+
+    /// The attacker's move that landed the first hit, as matched by `Config::starter_move`.
+    /// A throw for combos opened with a grab. `None` if it could not be found.
+    pub opener_move: Option<slp_parser::ActionState>,
+    /// `opener_move` classified, for filtering with `Config::opener`.
+    pub opener: Option<ComboOpener>,
+    /// The kind of move that dealt the final hit. `None` if it could not be found.
+    pub kill_move_category: Option<KillMoveCategory>,
+    /// The kill classified by `classify_ender`, for filtering with `Config::ender`. `None` if it could not be found.
+    pub ender: Option<ComboEnder>,
+    /// The blast zone the defender died through. `None` if their death state was not recognised.
+    pub kill_direction: Option<KillDirection>,
+    /// Fraction of the hits in the combo that were throws, from 0 to 1. High for chaingrabs.
+    pub grab_ratio: f32,
+    /// Number of grabs the attacker started during the combo, counting regrabs.
+    pub grab_count: usize,
+    /// Number of hits that connected, from the first hit to the kill, counting throws, see `Config::min_connected_hits`.
+    pub hit_count: usize,
+    /// Tech used to open the combo. `None` if there was none, or `Config::detect_tech` is off.
+    pub tech: Option<ComboTech>,
+    /// The defender was never actionable from the first hit to the last, see `Config::true_combos_only`.
+    pub is_true_combo: bool,
+}
+
+impl ComboMetadata {
+    pub fn damage(&self) -> f32 {
+        self.end_percent - self.start_percent
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum KillMoveCategory {
+    Smash,
+    Aerial,
+    Tilt,
+    Throw,
+    Special,
+    /// Jabs, dash attacks, ledge and getup attacks, and anything else.
+    Other,
+}
+
+impl KillMoveCategory {
+    /// Categorizes the attacker's action state on the frame their move hit.
+    pub fn from_action_state(state: slp_parser::ActionState) -> Self {
+        use slp_parser::{ActionState, StandardActionState::*, BroadState};
+
+        match state {
+            ActionState::Standard(
+                AttackS4Hi | AttackS4HiS | AttackS4S | AttackS4LwS | AttackS4Lw | AttackHi4 | AttackLw4
+            ) => KillMoveCategory::Smash,
+            ActionState::Standard(
+                AttackAirN | AttackAirF | AttackAirB | AttackAirHi | AttackAirLw
+            ) => KillMoveCategory::Aerial,
+            ActionState::Standard(
+                AttackS3Hi | AttackS3HiS | AttackS3S | AttackS3LwS | AttackS3Lw | AttackHi3 | AttackLw3
+            ) => KillMoveCategory::Tilt,
+            // throws are not attack states, the defender takes damage when they are released
+            ActionState::Standard(ThrowF | ThrowB | ThrowHi | ThrowLw) => KillMoveCategory::Throw,
+            _ if matches!(state.broad_state(), BroadState::Special(_)) => KillMoveCategory::Special,
+            _ => KillMoveCategory::Other,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KillMoveCategory::Smash => "smash",
+            KillMoveCategory::Aerial => "aerial",
+            KillMoveCategory::Tilt => "tilt",
+            KillMoveCategory::Throw => "throw",
+            KillMoveCategory::Special => "special",
+            KillMoveCategory::Other => "other",
+        }
+    }
+}
+
+/// The blast zone the defender was killed through, read from their first `Dead` action state.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum KillDirection {
+    Left,
+    Right,
+    Bottom,
+    Top(TopKillKind),
+}
+
+/// How a kill off the top blast zone played out.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TopKillKind {
+    /// Launched straight through the blast zone, `DeadUp` (state 3).
+    Regular,
+    /// Launched into the background with a twinkle, `DeadUpStar` and `DeadUpStarIce` (states 4 and 5).
+    StarKo,
+    /// Launched into the camera, `DeadUpFall` and the `DeadUpFallHitCamera*` and `DeadUpFallIce*` states
+    /// that follow it (states 6 to 10).
+    ScreenKo,
+}
+
+impl KillDirection {
+    /// `None` if `state` is not one of the death states, 0 to 10.
+    pub fn from_action_state(state: slp_parser::ActionState) -> Option<Self> {
+        use slp_parser::{ActionState, StandardActionState::*};
+
+        match state {
+            ActionState::Standard(DeadDown) => Some(KillDirection::Bottom),
+            ActionState::Standard(DeadLeft) => Some(KillDirection::Left),
+            ActionState::Standard(DeadRight) => Some(KillDirection::Right),
+            ActionState::Standard(DeadUp) => Some(KillDirection::Top(TopKillKind::Regular)),
+            ActionState::Standard(DeadUpStar | DeadUpStarIce) => Some(KillDirection::Top(TopKillKind::StarKo)),
+            ActionState::Standard(
+                DeadUpFall | DeadUpFallHitCamera | DeadUpFallHitCameraFlat | DeadUpFallIce | DeadUpFallHitCameraIce
+            ) => Some(KillDirection::Top(TopKillKind::ScreenKo)),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KillDirection::Left => "left",
+            KillDirection::Right => "right",
+            KillDirection::Bottom => "bottom",
+            KillDirection::Top(TopKillKind::Regular) => "top",
+            KillDirection::Top(TopKillKind::StarKo) => "starKo",
+            KillDirection::Top(TopKillKind::ScreenKo) => "screenKo",
+        }
+    }
+}
+
+/// The kind of move that opened a combo, from `ComboMetadata::opener_move`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ComboOpener {
+    /// A grab, including the throw that started the combo, e.g. a down throw.
+    Grab,
+    DownSmash,
+    ForwardSmash,
+    BackAir,
+    NeutralAir,
+    /// Any other move, by the attacker's action state.
+    Other(slp_parser::ActionState),
+}
+
+impl ComboOpener {
+    pub fn from_action_state(state: slp_parser::ActionState) -> Self {
+        use slp_parser::{ActionState, StandardActionState::*};
+
+        match state {
+            ActionState::Standard(Catch | CatchDash | ThrowF | ThrowB | ThrowHi | ThrowLw) => ComboOpener::Grab,
+            ActionState::Standard(AttackLw4) => ComboOpener::DownSmash,
+            ActionState::Standard(AttackS4Hi | AttackS4HiS | AttackS4S | AttackS4LwS | AttackS4Lw) => ComboOpener::ForwardSmash,
+            ActionState::Standard(AttackAirB) => ComboOpener::BackAir,
+            ActionState::Standard(AttackAirN) => ComboOpener::NeutralAir,
+            _ => ComboOpener::Other(state),
+        }
+    }
+
+    /// Parses the name of any opener but `Other`, as written by `name`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "grab" => Some(ComboOpener::Grab),
+            "downsmash" => Some(ComboOpener::DownSmash),
+            "forwardsmash" => Some(ComboOpener::ForwardSmash),
+            "backair" => Some(ComboOpener::BackAir),
+            "neutralair" => Some(ComboOpener::NeutralAir),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ComboOpener::Grab => "grab",
+            ComboOpener::DownSmash => "downsmash",
+            ComboOpener::ForwardSmash => "forwardsmash",
+            ComboOpener::BackAir => "backair",
+            ComboOpener::NeutralAir => "neutralair",
+            ComboOpener::Other(_) => "other",
+        }
+    }
+}
+
+/// Classifies the move the attacker landed on `first_hit_frame`, the defender's first frame of hitstun.
+///
+/// `None` if no attack or throw was found just before it, e.g. when the defender was hit by an item or
+/// walked into a lingering hitbox, or if `first_hit_frame` is past the end of the frames.
+/// Moves are told apart by the attacker's action state, which is what frames record, so `ComboOpener::Other`
+/// holds that rather than a move id.
+pub fn classify_opener(atk_frames: &[slp_parser::Frame], first_hit_frame: usize) -> Option<ComboOpener> {
+    combo_starter(atk_frames, first_hit_frame).map(ComboOpener::from_action_state)
+}
+
+/// The kind of move that ended a combo, i.e. the move that killed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ComboEnder {
+    BackAir,
+    ForwardSmash,
+    UpSmash,
+    /// The defender died off the bottom of the stage.
+    Spike,
+    /// The attacker was on the ledge shortly before the kill.
+    EdgeGuard,
+    /// Any other move, by the attacker's action state.
+    Other(slp_parser::ActionState),
+}
+
+impl ComboEnder {
+    /// Parses the name of any ender but `Other`, as written by `name`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "backair" => Some(ComboEnder::BackAir),
+            "forwardsmash" => Some(ComboEnder::ForwardSmash),
+            "upsmash" => Some(ComboEnder::UpSmash),
+            "spike" => Some(ComboEnder::Spike),
+            "edgeguard" => Some(ComboEnder::EdgeGuard),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ComboEnder::BackAir => "backair",
+            ComboEnder::ForwardSmash => "forwardsmash",
+            ComboEnder::UpSmash => "upsmash",
+            ComboEnder::Spike => "spike",
+            ComboEnder::EdgeGuard => "edgeguard",
+            ComboEnder::Other(_) => "other",
+        }
+    }
+}
+
+/// How many frames before the kill the attacker may have left the ledge for it to count as an edgeguard.
+const EDGEGUARD_WINDOW: usize = 60;
+
+/// Classifies the move the attacker killed with on `end_frame`, the frame it hit.
+///
+/// Unlike `classify_opener` this also needs the defender's frames, as a spike is told apart by the blast zone
+/// the defender died in. Spikes take precedence over edgeguards, and both over the move used.
+///
+/// The slices must be the same length, covering the same frames of the game. `None` if they are not,
+/// or if `end_frame` is past their end.
+pub fn classify_ender(
+    atk_frames: &[slp_parser::Frame],
+    def_frames: &[slp_parser::Frame],
+    end_frame: usize,
+) -> Option<ComboEnder> {
+    use slp_parser::{ActionState, StandardActionState::*, StandardBroadState};
+
+    if atk_frames.len() != def_frames.len() || end_frame >= atk_frames.len() { return None }
+
+    let direction = def_frames[end_frame..].iter()
+        .find(|f| f.state.broad_state() == StandardBroadState::Dead.into())
+        .and_then(|f| KillDirection::from_action_state(f.state));
+    if direction == Some(KillDirection::Bottom) { return Some(ComboEnder::Spike) }
+
+    let on_ledge = atk_frames[end_frame.saturating_sub(EDGEGUARD_WINDOW)..=end_frame].iter().any(|f| matches!(
+        f.state,
+        ActionState::Standard(
+            CliffCatch | CliffWait | CliffAttackQuick | CliffAttackSlow | CliffJumpQuick1 | CliffJumpSlow1
+        )
+    ));
+    if on_ledge { return Some(ComboEnder::EdgeGuard) }
+
+    Some(match atk_frames[end_frame].state {
+        ActionState::Standard(AttackAirB) => ComboEnder::BackAir,
+        ActionState::Standard(AttackS4Hi | AttackS4HiS | AttackS4S | AttackS4LwS | AttackS4Lw) => ComboEnder::ForwardSmash,
+        ActionState::Standard(AttackHi4) => ComboEnder::UpSmash,
+        state => ComboEnder::Other(state),
+    })
+}
+
+/// Movement tech used to open a combo. Only detected with `Config::detect_tech`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ComboTech {
+    /// A Fox or Falco shine, jump cancelled into a wavedash.
+    Waveshine,
+    /// A wavedash into the first hit.
+    Wavedash,
+}
+
+impl ComboTech {
+    pub fn name(self) -> &'static str {
+        match self {
+            ComboTech::Waveshine => "waveshine",
+            ComboTech::Wavedash => "wavedash",
+        }
+    }
+}
+
+/// The Melee frame number of the first stored frame, at index 0. Slippi and Dolphin playlists number frames
+/// from it, so that frame 0 is the first frame players can act.
+pub const FRAME_OFFSET: isize = -123;
+
+/// The Slippi frame number Dolphin uses for the frame at `index`.
+pub fn to_dolphin_frame(index: usize) -> isize {
+    index as isize + FRAME_OFFSET
+}
+
+/// The index of Slippi frame `frame`, or `None` if it is before the first stored frame.
+pub fn from_dolphin_frame(frame: isize) -> Option<usize> {
+    usize::try_from(frame.checked_sub(FRAME_OFFSET)?).ok()
+}
+
+/// An index into a replay's frame array.
+///
+/// Slippi numbers frames starting from `FRAME_OFFSET`, so index 0 is Slippi frame -123.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FrameIndex(pub usize);
+
+impl FrameIndex {
+    pub fn to_slippi_frame(&self) -> i64 { to_dolphin_frame(self.0) as i64 }
+
+    /// Frames before the first stored frame are clamped to it. Use `try_from_slippi_frame` to reject them.
+    #[deprecated(note = "clamps frames before `FRAME_OFFSET` to 0, use `try_from_slippi_frame`")]
+    pub fn from_slippi_frame(n: i64) -> Self { FrameIndex::try_from_slippi_frame(n).unwrap_or(FrameIndex(0)) }
+
+    /// `None` if `n` is before the first stored frame.
+    pub fn try_from_slippi_frame(n: i64) -> Option<Self> {
+        from_dolphin_frame(isize::try_from(n).ok()?).map(FrameIndex)
+    }
+}
+
+/// Sent through the optional progress channel of `target_path`.
+#[derive(Clone, Debug)]
+pub enum ProgressEvent {
+    /// Sent once after the directory walk, with the number of files that will be scanned.
+    TargetCount(usize),
+    /// Sent before `TargetCount` if the directory walk found no replays.
+    NoFilesFound,
+    /// Sent after each file is scanned.
+    FileScanned,
+    /// Sent as soon as a combo is found, before the scan finishes.
+    /// With `Config::max_combos` set, files already being scanned when the limit is reached still send their
+    /// combos, so more may be sent than are returned, and some of those sent may not be among the ones returned.
+    ComboFound(Combo),
+    /// Sent before `FileScanned` if the file could not be parsed. Counted in `ScanStats::parse_failures`.
+    ParseFailed(PathBuf),
+}
+
+/// Counts of what happened to each file during a scan.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScanStats {
+    /// Replay files found by the directory walk, or passed in directly.
+    pub files_discovered: usize,
+    /// Files fully parsed and searched for combos.
+    pub files_analyzed: usize,
+    /// Files whose players did not match the config's filters, so were never fully parsed.
+    pub files_skipped: usize,
+    /// Files that could not be parsed. Those whose game info parsed and matched the filters, but whose frames
+    /// did not, are also listed in `warnings` as likely corrupt.
+    pub parse_failures: usize,
+    /// Files older than `Config::min_version`, never fully parsed.
+    pub files_too_old: usize,
+    pub combos_found: usize,
+    /// Folders skipped by `Config::exclude_dirs`. They are never read, so the replays inside are not counted.
+    pub dirs_excluded: usize,
+    /// Files skipped because `Config::checkpoint` shows they were scanned by an earlier, interrupted run.
+    /// Included in `files_discovered`, and their combos in `combos_found`.
+    pub files_resumed: usize,
+    /// Time taken by the scan, including the directory walk.
+    pub elapsed: std::time::Duration,
+
+    /// Problems that did not stop the scan.
+    pub warnings: Vec<ScanWarning>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScanWarning {
+    /// A symlink in the directory walk points to nothing. It was skipped.
+    BrokenSymlink(PathBuf),
+    /// A folder in the directory walk could not be read. It was skipped.
+    UnreadableDirectory(PathBuf),
+    /// The checkpoint file could not be written, so an interrupted scan may not resume fully.
+    CheckpointWriteFailed(PathBuf),
+    /// The replay's game info matched the filters but its frames could not be parsed,
+    /// so it is probably truncated or corrupt.
+    GameParseFailed(PathBuf),
+    /// The replay does not have exactly two players, e.g. a solo replay loaded to practice. It was skipped.
+    /// Games with more than two players are not supported yet.
+    NotTwoPlayer(PathBuf),
+    /// A compressed `.slpz` replay could not be read, so the archive is probably corrupt.
+    /// Counted in `ScanStats::parse_failures`.
+    DecompressionFailed(PathBuf),
+    /// The replay has no frames for one of its two players, e.g. after an early disconnect. It was skipped.
+    MissingFrames(PathBuf),
+    /// The two players have a different number of frames. Both were cut to the shorter length and scanned.
+    FrameCountMismatch(PathBuf),
+    /// A display name or connect code is not valid Shift JIS. Its non ascii characters were replaced with U+FFFD
+    /// and the replay was scanned.
+    InvalidNames(PathBuf),
+    /// A replay that could not be parsed is shorter than its header says, usually because it was not fully
+    /// written or downloaded. Counted in `ScanStats::parse_failures`.
+    TruncatedFile(PathBuf),
+    /// A file that could not be parsed does not start like a replay. Counted in `ScanStats::parse_failures`.
+    HeaderInvalid(PathBuf),
+    /// The game, of this many frames, is no longer than the lead in, e.g. it was quit during the countdown,
+    /// so no combos were looked for. Counted in `ScanStats::files_analyzed`.
+    TooShort(PathBuf, usize),
+}
+
+impl std::fmt::Display for ScanWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScanWarning::BrokenSymlink(path) => write!(f, "Skipped broken symlink '{}'.", path.display()),
+            ScanWarning::UnreadableDirectory(path) => write!(f, "Could not read folder '{}'.", path.display()),
+            ScanWarning::CheckpointWriteFailed(path) => write!(f, "Could not write checkpoint '{}'.", path.display()),
+            ScanWarning::GameParseFailed(path) => write!(
+                f, "'{}' matched the filters but its frames could not be parsed, it may be corrupt.", path.display(),
+            ),
+            ScanWarning::NotTwoPlayer(path) => write!(f, "Skipped '{}', it is not a 2 player game.", path.display()),
+            ScanWarning::DecompressionFailed(path) => write!(f, "Could not decompress '{}', it may be corrupt.", path.display()),
+            ScanWarning::TruncatedFile(path) => write!(f, "'{}' is truncated, it may not have finished writing.", path.display()),
+            ScanWarning::HeaderInvalid(path) => write!(f, "'{}' is not a valid replay.", path.display()),
+            ScanWarning::TooShort(path, frames) => write!(
+                f, "Skipped '{}', its {} frames are too few to hold a combo and its lead in.", path.display(), frames,
+            ),
+            ScanWarning::InvalidNames(path) => write!(f, "Names in '{}' could not be fully decoded.", path.display()),
+            ScanWarning::MissingFrames(path) => write!(f, "Skipped '{}', a player has no frames.", path.display()),
+            ScanWarning::FrameCountMismatch(path) => write!(
+                f, "The players in '{}' have different numbers of frames, only the frames both have were scanned.", path.display(),
+            ),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum TargetPathError {
     PathNotFound,
+    /// A glob pattern matched no replay files.
+    NoMatches,
+    /// The path is a file without a replay extension, see `Config::extensions`.
+    NotAReplay,
+    /// The directory walk found no replays and `Config::strict` is set.
+    NoFilesFound,
+}
+
+impl std::fmt::Display for TargetPathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetPathError::PathNotFound => write!(f, "Path does not exist."),
+            TargetPathError::NoMatches => write!(f, "Pattern did not match any replays."),
+            TargetPathError::NotAReplay => write!(f, "File is not a replay."),
+            TargetPathError::NoFilesFound => write!(f, "No replay files found."),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Config {
     pub lead_in: usize,
     pub lead_out: usize,
+    /// Per character `lead_in` and `lead_out`, looked up by the attacker's character,
+    /// e.g. a longer lead in for Jigglypuff to show the setup. Characters not in the map use `lead_in` and `lead_out`.
+    pub lead_in_override: Option<HashMap<slp_parser::Character, usize>>,
+    pub lead_out_override: Option<HashMap<slp_parser::Character, usize>>,
 
     /// Must be between 0 and 1
     pub strictness: f32,
@@ -26,12 +572,185 @@ pub struct Config {
     pub opponent_character: Option<slp_parser::Character>,
     pub opponent_code: Option<String>,
     pub opponent_name: Option<String>,
+    /// Like `player_character` and `opponent_character`, but matching any of several characters, e.g. Fox or Falco.
+    /// Both the single character and the set must match when both are set.
+    pub player_characters: Option<Vec<slp_parser::Character>>,
+    pub opponent_characters: Option<Vec<slp_parser::Character>>,
+    /// Only scan games in which either player's connect code contains this, keeping both players' combos.
+    /// Unlike `player_code`, it does not matter who performed the combo.
+    pub either_player_code: Option<String>,
+
+    /// What to do when the defender hits the attacker partway through a combo.
+    /// `None` allows a strictness-derived amount of attacker hitstun, as before.
+    pub combo_breaker: Option<ComboBreakerMode>,
+
+    /// Number of threads used to scan files. `None` uses the available parallelism.
+    /// `Some(1)` scans files one at a time, in order.
+    pub threads: Option<usize>,
+
+    /// Only keep combos from games won by the player matched by `player_name`, `player_code` or `player_character`.
+    /// The winner is whoever took the other player's last stock. Timeouts and games ended early by LRAS or a
+    /// disconnect have no winner under this rule, so their combos are dropped.
+    /// Has no effect unless one of those player filters is set.
+    pub player_won_only: bool,
+
+    /// Only scan online games of this type. Offline games, replays from before Slippi 3.14.0 (which have no match id)
+    /// and compressed `.slpz` replays never match.
+    pub match_type: Option<MatchType>,
+
+    /// Skip replays recorded with a Slippi version older than this, as `(major, minor, patch)`.
+    /// Compressed `.slpz` replays have no readable version and are never skipped.
+    pub min_version: Option<(u8, u8, u8)>,
+
+    /// Only keep combos where the defender (or attacker) had at most this many stocks left when the combo ended.
+    /// Setting both to 1 keeps only kills where both players were on their last stock.
+    pub max_defender_stocks: Option<u8>,
+    pub max_attacker_stocks: Option<u8>,
+
+    /// Only keep combos started while the attacker was at this percent or higher, e.g. comebacks at 120%.
+    pub min_attacker_percent: Option<f32>,
+    /// Only keep combos started while the attacker was on their last stock.
+    pub attacker_last_stock: bool,
+
+    /// Follow symlinks to directories during the directory walk. Each directory is walked at most once,
+    /// so symlink cycles terminate. Symlinks to files are always followed.
+    pub follow_symlinks: bool,
+
+    /// Fail with `TargetPathError::NoFilesFound` when the directory walk finds no replays, instead of
+    /// returning no combos, so that scripts do not write an empty playlist.
+    pub strict: bool,
+
+    /// How many folders deep the directory walk goes. `Some(1)` only scans files directly in the given folder.
+    /// `None` has no limit.
+    pub max_depth: Option<usize>,
+
+    /// Folders skipped by the directory walk, matched by name with `*`, `?` and `[...]` wildcards,
+    /// e.g. `_practice_mode` or `*backup*`. Patterns containing a `/`, like `archive/corrupted`,
+    /// are matched against the trailing folders of the path instead.
+    pub exclude_dirs: Vec<String>,
+
+    /// File extensions the directory walk treats as replays, matched case-insensitively against the end of the
+    /// file name, so multi-part extensions like `slp.bak` work. `None` accepts `slp` and `slpz`.
+    pub extensions: Option<Vec<String>>,
+
+    /// Only keep combos opened by one of these moves, e.g. `AttackAirB` for a shield drop bair.
+    /// Throw states (`ThrowF`, ...) match combos started by that throw. Not read by `from_json` or `from_env`.
+    pub starter_move: Option<Vec<slp_parser::ActionState>>,
+
+    /// Only keep clean punishes: reject combos in which the attacker took damage, was put in hitstun,
+    /// or (unless `flawless_allow_shield_hits`) was hit in shield between the first hit and the kill.
+    /// Stricter than the strictness-derived attacker hitstun budget, which allows some hits.
+    pub flawless: bool,
+    /// With `flawless`, allow the attacker to block hits with their shield.
+    pub flawless_allow_shield_hits: bool,
+    /// Reject combos in which the attacker took more than this much damage between the first hit and the kill,
+    /// e.g. combos full of trades. A looser `flawless` that allows a few light hits.
+    pub max_attacker_damage: Option<f32>,
+
+    /// Only keep true combos, in which the defender was in hitstun, grabbed, thrown or teching
+    /// between every hit, rather than strings they could have escaped.
+    pub true_combos_only: bool,
+    /// Whether tumble counts as escapable when checking for a true combo. Defenders can jump or attack out of
+    /// tumble, so by default it does, and a combo that lets the defender fall in tumble is a string.
+    pub tumble_breaks_true_combo: bool,
+
+    /// Tag combos opened with a waveshine or wavedash in `ComboMetadata::tech`. Off by default, since it is
+    /// only useful for a few characters.
+    pub detect_tech: bool,
+
+    /// Only keep punishes out of shield: the attacker must have been shielding or in shieldstun within this many
+    /// frames before the first hit. Around 15 frames allows for a jump or a dash before the first hit.
+    pub out_of_shield_frames: Option<usize>,
+
+    /// Only keep combos opened by this kind of move, e.g. `ComboOpener::Grab` for throw combos.
+    /// A coarser `starter_move`.
+    pub opener: Option<ComboOpener>,
+
+    /// Only keep combos ended by this kind of kill, e.g. `ComboEnder::Spike`.
+    pub ender: Option<ComboEnder>,
+
+    /// Reject combos in which the attacker used fewer than this many different moves.
+    pub min_distinct_moves: Option<usize>,
+    /// Reject combos in which fewer than this many hits connected. Each hit of a multi-hit move counts and whiffs don't,
+    /// so a Peach dair is one of the attacks counted by `min_attacker_attacks` but several connected hits.
+    /// Throws count as hits.
+    pub min_connected_hits: Option<usize>,
+    /// Reject combos in which the attacker started fewer than this many attacks or specials, so that damage the
+    /// defender took by running into a lingering hitbox is not counted as a combo. Replaces the strictness-derived
+    /// `ComboHeuristics::min_attacker_attack_actions`. Attacks are counted from the attacker's action states,
+    /// not their controller inputs, so a held button counts once per move.
+    pub min_attacker_attacks: Option<usize>,
+
+    /// Stop the scan once this many combos have been found, for a quick preview of a large folder.
+    /// Files left unscanned are still counted in `ScanStats::files_discovered`. At most `max_combos` are returned.
+    pub max_combos: Option<usize>,
+
+    /// Reject combos with more than this many frames between consecutive hits. Throws count as hits.
+    pub max_hit_gap_frames: Option<usize>,
+
+    /// Skip games in which the defender took less than this much damage in total, e.g. games against an idle opponent.
+    pub min_total_game_damage: Option<f32>,
+
+    /// Skip games shorter or longer than this many frames. Very short games are usually quit during the first
+    /// stock, and very long ones are often friendlies or a player left idle.
+    pub min_game_duration_frames: Option<usize>,
+    pub max_game_duration_frames: Option<usize>,
+
+    /// Reject combos whose `ComboMetadata::grab_ratio` is above this, e.g. `0.5` to drop chaingrabs while keeping
+    /// a chaingrab finished with a few hits. Unlike the consecutive grab limit this does not depend on strictness.
+    pub max_grab_ratio: Option<f32>,
+
+    /// Record each scanned file and its combos in this file, so an interrupted scan can be rerun and
+    /// resume where it left off. Files listed in it are not scanned again; delete it to start over.
+    /// Combo metadata is not stored, so combos from resumed files have `metadata: None`, and exports that use it
+    /// (csv, jsonl, sqlite) leave those columns empty for them. Files whose path is not valid UTF-8 are not
+    /// recorded, and are rescanned on resume.
+    pub checkpoint: Option<PathBuf>,
+    /// Number of scanned files between writes to the checkpoint.
+    pub checkpoint_interval: usize,
+}
+
+/// The kind of online game, from the replay's match id.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MatchType {
+    Ranked,
+    Unranked,
+    Direct,
+}
+
+impl MatchType {
+    /// Parses "ranked", "unranked" or "direct".
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "ranked" => Some(MatchType::Ranked),
+            "unranked" => Some(MatchType::Unranked),
+            "direct" => Some(MatchType::Direct),
+            _ => None,
+        }
+    }
+
+    /// Match ids look like `mode.ranked-2023-07-01T18:37:44.88-0`.
+    fn from_match_id(match_id: &str) -> Option<Self> {
+        let mode = match_id.strip_prefix("mode.")?;
+        let mode = mode.split('-').next()?;
+        MatchType::parse(mode)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ComboBreakerMode {
+    /// Reject any combo in which the attacker was put in hitstun.
+    Reject,
+    /// Cut the combo so that it starts after the last time the attacker was put in hitstun.
+    Truncate,
 }
 
 impl Config {
     pub const DEFAULT: Self = Config {
         lead_in: 30,
         lead_out: 0,
+        lead_in_override: None,
+        lead_out_override: None,
         strictness: 0.5,
 
         player_character: None,
@@ -40,7 +759,629 @@ impl Config {
         opponent_character: None,
         opponent_code: None,
         opponent_name: None,
+        player_characters: None,
+        opponent_characters: None,
+        either_player_code: None,
+
+        combo_breaker: None,
+
+        threads: None,
+
+        player_won_only: false,
+
+        match_type: None,
+        min_version: None,
+
+        max_defender_stocks: None,
+        max_attacker_stocks: None,
+        min_attacker_percent: None,
+        attacker_last_stock: false,
+
+        follow_symlinks: false,
+        strict: false,
+        max_depth: None,
+        exclude_dirs: Vec::new(),
+        extensions: None,
+        starter_move: None,
+        flawless: false,
+        flawless_allow_shield_hits: false,
+        max_attacker_damage: None,
+        true_combos_only: false,
+        tumble_breaks_true_combo: true,
+        detect_tech: false,
+        out_of_shield_frames: None,
+        opener: None,
+        ender: None,
+        min_distinct_moves: None,
+        min_attacker_attacks: None,
+        min_connected_hits: None,
+        max_combos: None,
+        max_hit_gap_frames: None,
+        min_total_game_damage: None,
+        min_game_duration_frames: None,
+        max_game_duration_frames: None,
+        max_grab_ratio: None,
+        checkpoint: None,
+        checkpoint_interval: 100,
     };
+
+    /// The number of threads a scan with this config will use, at most.
+    pub fn thread_count(&self) -> usize {
+        match self.threads {
+            Some(n) => n.max(1),
+            None => std::thread::available_parallelism().map_or(8, |n| n.get()),
+        }
+    }
+
+    /// True if `player_character` and `player_characters` both allow this character.
+    pub fn allows_player_character(&self, character: slp_parser::Character) -> bool {
+        character_allowed(self.player_character, self.player_characters.as_deref(), character)
+    }
+
+    /// True if `opponent_character` and `opponent_characters` both allow this character.
+    pub fn allows_opponent_character(&self, character: slp_parser::Character) -> bool {
+        character_allowed(self.opponent_character, self.opponent_characters.as_deref(), character)
+    }
+
+    /// The lead in for combos by this character, from `lead_in_override` or else `lead_in`.
+    pub fn effective_lead_in(&self, character: slp_parser::Character) -> usize {
+        self.lead_in_override.as_ref().and_then(|o| o.get(&character).copied()).unwrap_or(self.lead_in)
+    }
+
+    /// The lead out for combos by this character, from `lead_out_override` or else `lead_out`.
+    pub fn effective_lead_out(&self, character: slp_parser::Character) -> usize {
+        self.lead_out_override.as_ref().and_then(|o| o.get(&character).copied()).unwrap_or(self.lead_out)
+    }
+
+    /// Reads a config from a json object whose keys are the field names of `Config`.
+    /// Missing or null fields keep their value from `Config::DEFAULT`.
+    pub fn from_json(config_json: &str) -> Result<Config, ConfigJsonError> {
+        let value = json::parse(config_json).map_err(|e| ConfigJsonError::JsonParseError(e))?;
+        Config::from_json_value(&value)
+    }
+
+    pub fn from_json_value(value: &json::JsonValue) -> Result<Config, ConfigJsonError> {
+        if !value.is_object() { return Err(ConfigJsonError::NotAnObject) }
+
+        let field = move |name: &'static str| match &value[name] {
+            json::JsonValue::Null => None,
+            v => Some((name, v)),
+        };
+        let string = move |name: &'static str| -> Result<Option<String>, ConfigJsonError> {
+            field(name)
+                .map(|(name, v)| v.as_str().map(str::to_string).ok_or(ConfigJsonError::InvalidField(name)))
+                .transpose()
+        };
+        let character = move |name: &'static str| -> Result<Option<slp_parser::Character>, ConfigJsonError> {
+            field(name)
+                .map(|(name, v)| v.as_str().and_then(parse_character).ok_or(ConfigJsonError::InvalidField(name)))
+                .transpose()
+        };
+        let characters = move |name: &'static str| -> Result<Option<Vec<slp_parser::Character>>, ConfigJsonError> {
+            field(name)
+                .map(|(name, v)| {
+                    if !v.is_array() { return Err(ConfigJsonError::InvalidField(name)) }
+                    v.members()
+                        .map(|c| c.as_str().and_then(parse_character).ok_or(ConfigJsonError::InvalidField(name)))
+                        .collect()
+                })
+                .transpose()
+        };
+        // e.g. `{"jigglypuff": 60, "peach": 45}`
+        let character_frames = move |name: &'static str| -> Result<Option<HashMap<slp_parser::Character, usize>>, ConfigJsonError> {
+            field(name)
+                .map(|(name, v)| {
+                    if !v.is_object() { return Err(ConfigJsonError::InvalidField(name)) }
+                    v.entries()
+                        .map(|(c, n)| match (parse_character(c), n.as_usize()) {
+                            (Some(c), Some(n)) => Ok((c, n)),
+                            _ => Err(ConfigJsonError::InvalidField(name)),
+                        })
+                        .collect()
+                })
+                .transpose()
+        };
+
+        let mut config = Config::DEFAULT;
+
+        if let Some((name, v)) = field("lead_in") {
+            config.lead_in = v.as_usize().ok_or(ConfigJsonError::InvalidField(name))?;
+        }
+        if let Some((name, v)) = field("lead_out") {
+            config.lead_out = v.as_usize().ok_or(ConfigJsonError::InvalidField(name))?;
+        }
+        config.lead_in_override = character_frames("lead_in_override")?;
+        config.lead_out_override = character_frames("lead_out_override")?;
+        if let Some((name, v)) = field("strictness") {
+            config.strictness = v.as_f32()
+                .filter(|s| (0.0..=1.0).contains(s))
+                .ok_or(ConfigJsonError::InvalidField(name))?;
+        }
+
+        config.player_character = character("player_character")?;
+        config.player_code = string("player_code")?;
+        config.player_name = string("player_name")?;
+        config.opponent_character = character("opponent_character")?;
+        config.player_characters = characters("player_characters")?;
+        config.opponent_characters = characters("opponent_characters")?;
+        config.either_player_code = string("either_player_code")?;
+        config.opponent_code = string("opponent_code")?;
+        config.opponent_name = string("opponent_name")?;
+
+        if let Some((name, v)) = field("threads") {
+            config.threads = Some(v.as_usize().filter(|n| *n >= 1).ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("player_won_only") {
+            config.player_won_only = v.as_bool().ok_or(ConfigJsonError::InvalidField(name))?;
+        }
+        if let Some((name, v)) = field("match_type") {
+            config.match_type = Some(v.as_str().and_then(MatchType::parse).ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("min_version") {
+            config.min_version = Some(v.as_str().and_then(parse_version).ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("max_defender_stocks") {
+            config.max_defender_stocks = Some(v.as_u8().ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("max_attacker_stocks") {
+            config.max_attacker_stocks = Some(v.as_u8().ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("min_attacker_percent") {
+            config.min_attacker_percent = Some(v.as_f32().ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("attacker_last_stock") {
+            config.attacker_last_stock = v.as_bool().ok_or(ConfigJsonError::InvalidField(name))?;
+        }
+        if let Some((name, v)) = field("follow_symlinks") {
+            config.follow_symlinks = v.as_bool().ok_or(ConfigJsonError::InvalidField(name))?;
+        }
+        if let Some((name, v)) = field("strict") {
+            config.strict = v.as_bool().ok_or(ConfigJsonError::InvalidField(name))?;
+        }
+        if let Some((name, v)) = field("max_depth") {
+            config.max_depth = Some(v.as_usize().filter(|n| *n >= 1).ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("exclude_dirs") {
+            if !v.is_array() { return Err(ConfigJsonError::InvalidField(name)) }
+            config.exclude_dirs = v.members()
+                .map(|p| p.as_str().map(str::to_string).ok_or(ConfigJsonError::InvalidField(name)))
+                .collect::<Result<_, _>>()?;
+        }
+        if let Some((name, v)) = field("extensions") {
+            if !v.is_array() { return Err(ConfigJsonError::InvalidField(name)) }
+            config.extensions = Some(v.members()
+                .map(|e| e.as_str().map(str::to_string).ok_or(ConfigJsonError::InvalidField(name)))
+                .collect::<Result<_, _>>()?);
+        }
+        if let Some((name, v)) = field("flawless") {
+            config.flawless = v.as_bool().ok_or(ConfigJsonError::InvalidField(name))?;
+        }
+        if let Some((name, v)) = field("flawless_allow_shield_hits") {
+            config.flawless_allow_shield_hits = v.as_bool().ok_or(ConfigJsonError::InvalidField(name))?;
+        }
+        if let Some((name, v)) = field("max_attacker_damage") {
+            config.max_attacker_damage = Some(v.as_f32().ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("true_combos_only") {
+            config.true_combos_only = v.as_bool().ok_or(ConfigJsonError::InvalidField(name))?;
+        }
+        if let Some((name, v)) = field("tumble_breaks_true_combo") {
+            config.tumble_breaks_true_combo = v.as_bool().ok_or(ConfigJsonError::InvalidField(name))?;
+        }
+        if let Some((name, v)) = field("detect_tech") {
+            config.detect_tech = v.as_bool().ok_or(ConfigJsonError::InvalidField(name))?;
+        }
+        if let Some((name, v)) = field("out_of_shield_frames") {
+            config.out_of_shield_frames = Some(v.as_usize().ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("opener") {
+            config.opener = Some(v.as_str().and_then(ComboOpener::parse).ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("ender") {
+            config.ender = Some(v.as_str().and_then(ComboEnder::parse).ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("min_distinct_moves") {
+            config.min_distinct_moves = Some(v.as_usize().ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("min_attacker_attacks") {
+            config.min_attacker_attacks = Some(v.as_usize().ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("min_connected_hits") {
+            config.min_connected_hits = Some(v.as_usize().ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("max_combos") {
+            config.max_combos = Some(v.as_usize().ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("max_hit_gap_frames") {
+            config.max_hit_gap_frames = Some(v.as_usize().ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("min_total_game_damage") {
+            config.min_total_game_damage = Some(v.as_f32().ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("min_game_duration_frames") {
+            config.min_game_duration_frames = Some(v.as_usize().ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("max_game_duration_frames") {
+            config.max_game_duration_frames = Some(v.as_usize().ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("max_grab_ratio") {
+            config.max_grab_ratio = Some(v.as_f32()
+                .filter(|r| (0.0..=1.0).contains(r))
+                .ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("checkpoint") {
+            config.checkpoint = Some(v.as_str().map(PathBuf::from).ok_or(ConfigJsonError::InvalidField(name))?);
+        }
+        if let Some((name, v)) = field("checkpoint_interval") {
+            config.checkpoint_interval = v.as_usize().filter(|n| *n >= 1).ok_or(ConfigJsonError::InvalidField(name))?;
+        }
+        if let Some((name, v)) = field("combo_breaker") {
+            config.combo_breaker = match v.as_str() {
+                Some("reject") => Some(ComboBreakerMode::Reject),
+                Some("truncate") => Some(ComboBreakerMode::Truncate),
+                _ => return Err(ConfigJsonError::InvalidField(name)),
+            };
+        }
+
+        Ok(config)
+    }
+
+    /// Reads a config from environment variables named after its fields, upper cased with an `SLP_` prefix,
+    /// e.g. `SLP_STRICTNESS`, `SLP_PLAYER_CHARACTER` or `SLP_THREADS`, so it can be set in CI or containers.
+    /// Values are written as on the CLI: `SLP_COMBO_BREAKER` is `reject` or `truncate`, booleans are `true` or `false`
+    /// and lists like `SLP_EXCLUDE_DIRS` and `SLP_PLAYER_CHARACTERS` are comma separated. `starter_move`, `lead_in_override` and
+    /// `lead_out_override` cannot be set this way.
+    /// Unset or empty variables keep their value from `Config::DEFAULT`.
+    pub fn from_env() -> Result<Config, ConfigEnvError> {
+        let mut config = Config::DEFAULT;
+
+        if let Some(n) = env_parse("SLP_LEAD_IN", |v| v.parse().ok())? { config.lead_in = n; }
+        if let Some(n) = env_parse("SLP_LEAD_OUT", |v| v.parse().ok())? { config.lead_out = n; }
+        if let Some(s) = env_parse("SLP_STRICTNESS", |v| v.parse::<f32>().ok().filter(|s| (0.0..=1.0).contains(s)))? {
+            config.strictness = s;
+        }
+
+        config.player_character = env_parse("SLP_PLAYER_CHARACTER", parse_character)?;
+        config.player_code = env_var("SLP_PLAYER_CODE")?;
+        config.player_name = env_var("SLP_PLAYER_NAME")?;
+        config.opponent_character = env_parse("SLP_OPPONENT_CHARACTER", parse_character)?;
+        config.player_characters = env_parse("SLP_PLAYER_CHARACTERS", parse_characters)?;
+        config.opponent_characters = env_parse("SLP_OPPONENT_CHARACTERS", parse_characters)?;
+        config.either_player_code = env_var("SLP_EITHER_PLAYER_CODE")?;
+        config.opponent_code = env_var("SLP_OPPONENT_CODE")?;
+        config.opponent_name = env_var("SLP_OPPONENT_NAME")?;
+
+        config.combo_breaker = env_parse("SLP_COMBO_BREAKER", |v| match v {
+            "reject" => Some(ComboBreakerMode::Reject),
+            "truncate" => Some(ComboBreakerMode::Truncate),
+            _ => None,
+        })?;
+        config.threads = env_parse("SLP_THREADS", |v| v.parse::<usize>().ok().filter(|n| *n >= 1))?;
+        if let Some(b) = env_parse("SLP_PLAYER_WON_ONLY", |v| v.parse().ok())? { config.player_won_only = b; }
+        config.match_type = env_parse("SLP_MATCH_TYPE", MatchType::parse)?;
+        config.min_version = env_parse("SLP_MIN_VERSION", parse_version)?;
+        config.max_defender_stocks = env_parse("SLP_MAX_DEFENDER_STOCKS", |v| v.parse().ok())?;
+        config.max_attacker_stocks = env_parse("SLP_MAX_ATTACKER_STOCKS", |v| v.parse().ok())?;
+        config.min_attacker_percent = env_parse("SLP_MIN_ATTACKER_PERCENT", |v| v.parse().ok())?;
+        if let Some(b) = env_parse("SLP_ATTACKER_LAST_STOCK", |v| v.parse().ok())? { config.attacker_last_stock = b; }
+        if let Some(b) = env_parse("SLP_FOLLOW_SYMLINKS", |v| v.parse().ok())? { config.follow_symlinks = b; }
+        if let Some(b) = env_parse("SLP_STRICT", |v| v.parse().ok())? { config.strict = b; }
+        config.max_depth = env_parse("SLP_MAX_DEPTH", |v| v.parse::<usize>().ok().filter(|n| *n >= 1))?;
+        config.extensions = env_var("SLP_EXTENSIONS")?
+            .map(|e| e.split(',').map(|e| e.trim().to_string()).filter(|e| !e.is_empty()).collect());
+        if let Some(b) = env_parse("SLP_FLAWLESS", |v| v.parse().ok())? { config.flawless = b; }
+        if let Some(b) = env_parse("SLP_FLAWLESS_ALLOW_SHIELD_HITS", |v| v.parse().ok())? { config.flawless_allow_shield_hits = b; }
+        config.max_attacker_damage = env_parse("SLP_MAX_ATTACKER_DAMAGE", |v| v.parse().ok())?;
+        if let Some(b) = env_parse("SLP_TRUE_COMBOS_ONLY", |v| v.parse().ok())? { config.true_combos_only = b; }
+        if let Some(b) = env_parse("SLP_TUMBLE_BREAKS_TRUE_COMBO", |v| v.parse().ok())? { config.tumble_breaks_true_combo = b; }
+        if let Some(b) = env_parse("SLP_DETECT_TECH", |v| v.parse().ok())? { config.detect_tech = b; }
+        config.out_of_shield_frames = env_parse("SLP_OUT_OF_SHIELD_FRAMES", |v| v.parse().ok())?;
+        config.opener = env_parse("SLP_OPENER", ComboOpener::parse)?;
+        config.ender = env_parse("SLP_ENDER", ComboEnder::parse)?;
+        config.min_distinct_moves = env_parse("SLP_MIN_DISTINCT_MOVES", |v| v.parse().ok())?;
+        config.min_attacker_attacks = env_parse("SLP_MIN_ATTACKER_ATTACKS", |v| v.parse().ok())?;
+        config.min_connected_hits = env_parse("SLP_MIN_CONNECTED_HITS", |v| v.parse().ok())?;
+        config.max_combos = env_parse("SLP_MAX_COMBOS", |v| v.parse().ok())?;
+        config.max_hit_gap_frames = env_parse("SLP_MAX_HIT_GAP_FRAMES", |v| v.parse().ok())?;
+        config.min_total_game_damage = env_parse("SLP_MIN_TOTAL_GAME_DAMAGE", |v| v.parse().ok())?;
+        config.min_game_duration_frames = env_parse("SLP_MIN_GAME_DURATION_FRAMES", |v| v.parse().ok())?;
+        config.max_game_duration_frames = env_parse("SLP_MAX_GAME_DURATION_FRAMES", |v| v.parse().ok())?;
+        config.max_grab_ratio = env_parse("SLP_MAX_GRAB_RATIO", |v| v.parse::<f32>().ok().filter(|r| (0.0..=1.0).contains(r)))?;
+        config.checkpoint = env_var("SLP_CHECKPOINT")?.map(PathBuf::from);
+        if let Some(n) = env_parse("SLP_CHECKPOINT_INTERVAL", |v| v.parse::<usize>().ok().filter(|n| *n >= 1))? {
+            config.checkpoint_interval = n;
+        }
+        if let Some(dirs) = env_var("SLP_EXCLUDE_DIRS")? {
+            config.exclude_dirs = dirs.split(',').map(|d| d.trim().to_string()).filter(|d| !d.is_empty()).collect();
+        }
+
+        Ok(config)
+    }
+}
+
+fn env_var(name: &'static str) -> Result<Option<String>, ConfigEnvError> {
+    match std::env::var(name) {
+        Ok(v) if v.is_empty() => Ok(None),
+        Ok(v) => Ok(Some(v)),
+        Err(std::env::VarError::NotPresent) => Ok(None),
+        Err(std::env::VarError::NotUnicode(v)) => Err(ConfigEnvError { name, value: v.to_string_lossy().into_owned() }),
+    }
+}
+
+fn env_parse<T>(name: &'static str, parse: impl FnOnce(&str) -> Option<T>) -> Result<Option<T>, ConfigEnvError> {
+    match env_var(name)? {
+        Some(value) => match parse(&value) {
+            Some(v) => Ok(Some(v)),
+            None => Err(ConfigEnvError { name, value }),
+        },
+        None => Ok(None),
+    }
+}
+
+/// An environment variable read by `Config::from_env` had an invalid value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigEnvError {
+    pub name: &'static str,
+    pub value: String,
+}
+
+impl std::fmt::Display for ConfigEnvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid value '{}' for environment variable {}.", self.value, self.name)
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigJsonError {
+    JsonParseError(json::Error),
+    NotAnObject,
+    InvalidField(&'static str),
+}
+
+impl std::fmt::Display for ConfigJsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigJsonError::JsonParseError(e) => write!(f, "Invalid json: {}", e),
+            ConfigJsonError::NotAnObject => write!(f, "Config must be a json object."),
+            ConfigJsonError::InvalidField(name) => write!(f, "Invalid value for config field '{}'.", name),
+        }
+    }
+}
+
+/// Parses a replay version such as "3.14.0" into `(major, minor, patch)`. A missing patch is 0.
+pub fn parse_version(version: &str) -> Option<(u8, u8, u8)> {
+    let mut parts = version.trim().trim_start_matches('v').split('.').map(|p| p.parse::<u8>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next()?.ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+    if parts.next().is_some() { return None }
+    Some((major, minor, patch))
+}
+
+/// Character names and abbreviations accepted by `parse_character`.
+/// Names are matched after lowercasing and removing spaces, dashes, underscores, dots and ampersands.
+const CHARACTER_ALIASES: &[(&str, slp_parser::Character)] = {
+    use slp_parser::Character::*;
+    &[
+        ("mario", Mario), ("drmario", DrMario), ("doc", DrMario), ("luigi", Luigi),
+        ("fox", Fox), ("falco", Falco),
+        ("captainfalcon", CaptainFalcon), ("falcon", CaptainFalcon), ("cf", CaptainFalcon),
+        ("donkeykong", DonkeyKong), ("dk", DonkeyKong),
+        ("kirby", Kirby), ("bowser", Bowser),
+        ("link", Link), ("younglink", YoungLink), ("yl", YoungLink), ("ylink", YoungLink),
+        ("sheik", Sheik), ("zelda", Zelda),
+        ("ness", Ness), ("peach", Peach),
+        ("iceclimbers", Popo), ("ics", Popo), ("ic", Popo), ("popo", Popo), ("nana", Nana),
+        ("pikachu", Pikachu), ("pika", Pikachu), ("pichu", Pichu),
+        ("samus", Samus), ("yoshi", Yoshi),
+        ("jigglypuff", Jigglypuff), ("puff", Jigglypuff), ("jiggs", Jigglypuff),
+        ("mewtwo", Mewtwo), ("m2", Mewtwo),
+        ("marth", Marth), ("roy", Roy),
+        ("ganondorf", Ganondorf), ("ganon", Ganondorf),
+        ("gameandwatch", GameAndWatch), ("mrgameandwatch", GameAndWatch), ("gnw", GameAndWatch), ("gw", GameAndWatch),
+        // "game & watch" once the ampersand is removed
+        ("gamewatch", GameAndWatch), ("mrgamewatch", GameAndWatch), ("gamenwatch", GameAndWatch), ("mrgnw", GameAndWatch),
+    ]
+};
+
+/// The full character names accepted by `parse_character`, for use in error messages.
+pub const CHARACTER_NAMES: &[&str] = &[
+    "mario", "drmario", "luigi", "fox", "falco", "captainfalcon", "donkeykong", "kirby",
+    "bowser", "link", "younglink", "sheik", "zelda", "ness", "peach", "iceclimbers", "nana",
+    "pikachu", "pichu", "samus", "yoshi", "jigglypuff", "mewtwo", "marth", "roy", "ganondorf",
+    "gameandwatch",
+];
+
+/// Parses a character from a common name or abbreviation, e.g. "Fox", "falcon", "ics", "dk".
+pub fn parse_character(name: &str) -> Option<slp_parser::Character> {
+    let name = name.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_' | '.' | '&'))
+        .collect::<String>()
+        .to_lowercase();
+
+    CHARACTER_ALIASES.iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, c)| *c)
+}
+
+/// Parses a comma separated list of characters, e.g. "fox, falco". `None` if any of them is unknown.
+pub fn parse_characters(names: &str) -> Option<Vec<slp_parser::Character>> {
+    names.split(',')
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(parse_character)
+        .collect()
+}
+
+/// The character Sheik and Zelda can transform into mid game.
+fn transform_partner(character: slp_parser::Character) -> Option<slp_parser::Character> {
+    use slp_parser::Character;
+
+    match character {
+        Character::Sheik => Some(Character::Zelda),
+        Character::Zelda => Some(Character::Sheik),
+        _ => None,
+    }
+}
+
+fn character_allowed(
+    single: Option<slp_parser::Character>,
+    set: Option<&[slp_parser::Character]>,
+    character: slp_parser::Character,
+) -> bool {
+    single.map_or(true, |c| c == character) && set.map_or(true, |s| s.contains(&character))
+}
+
+/// Per frame lookups computed once per game, so that `combo_start` can find where each kill's combo starts
+/// without walking back over the frames before it. Entries for frame `g` only depend on frames up to `g`,
+/// so one lookup serves every prefix of the game's frames.
+struct ComboLookup {
+    /// The most recent frame at or before `g` on which the defender was being hit, or was on the ground
+    /// or attacking, i.e. the potential end of a combo.
+    last_hit_end: Vec<Option<usize>>,
+    /// The most recent frame at or before `g` on which the attacker was in hitstun, i.e. a combo breaker.
+    last_attacker_hitstun: Vec<Option<usize>>,
+    /// Number of frames up to and including `g` on which the attacker was in hitstun.
+    attacker_hitstun_count: Vec<usize>,
+    /// Every frame on which the attacker was in hitstun, in order, indexed by `attacker_hitstun_count`.
+    attacker_hitstun_frames: Vec<usize>,
+    /// The most recent frame at or before `g` that ends a run of `max_defender_consecutive_actionable`
+    /// frames in which the defender was free to act.
+    last_defender_escape: Vec<Option<usize>>,
+    /// The first frame at or after `g` on which the defender was in hitstun.
+    next_defender_hitstun: Vec<Option<usize>>,
+    /// `ComboHeuristics::max_defender_consecutive_actionable`, which `last_defender_escape` was computed for.
+    max_defender_consecutive_actionable: usize,
+}
+
+impl ComboLookup {
+    /// `atk_frame` and `def_frame` should be the same length, the lookup covers the shorter of the two.
+    fn new(atk_frame: &[slp_parser::Frame], def_frame: &[slp_parser::Frame], config: &Config) -> Self {
+        use slp_parser::{BroadState, StandardBroadState};
+
+        let len = atk_frame.len().min(def_frame.len());
+        let max_actionable = ComboHeuristics::from_strictness(config.strictness).max_defender_consecutive_actionable;
+        let hitstun = |f: &slp_parser::Frame| f.state.broad_state() == BroadState::Standard(StandardBroadState::Hitstun);
+
+        let mut lookup = ComboLookup {
+            last_hit_end: Vec::with_capacity(len),
+            last_attacker_hitstun: Vec::with_capacity(len),
+            attacker_hitstun_count: Vec::with_capacity(len),
+            attacker_hitstun_frames: Vec::new(),
+            last_defender_escape: Vec::with_capacity(len),
+            next_defender_hitstun: vec![None; len],
+            max_defender_consecutive_actionable: max_actionable,
+        };
+
+        let mut last_hit_end = None;
+        let mut last_escape = None;
+        let mut actionable_run = 0;
+        for f in 0..len {
+            if matches!(
+                def_frame[f].state.broad_state(),
+                BroadState::Standard(StandardBroadState::Hitstun | StandardBroadState::Ground | StandardBroadState::Attack)
+            ) {
+                last_hit_end = Some(f);
+            }
+            lookup.last_hit_end.push(last_hit_end);
+
+            if hitstun(&atk_frame[f]) { lookup.attacker_hitstun_frames.push(f); }
+            lookup.last_attacker_hitstun.push(lookup.attacker_hitstun_frames.last().copied());
+            lookup.attacker_hitstun_count.push(lookup.attacker_hitstun_frames.len());
+
+            actionable_run = if defender_actionable(&def_frame[f]) { actionable_run + 1 } else { 0 };
+            if actionable_run >= max_actionable { last_escape = Some(f); }
+            lookup.last_defender_escape.push(last_escape);
+        }
+
+        let mut next_hitstun = None;
+        for f in (0..len).rev() {
+            if hitstun(&def_frame[f]) { next_hitstun = Some(f); }
+            lookup.next_defender_hitstun[f] = next_hitstun;
+        }
+
+        lookup
+    }
+
+    fn len(&self) -> usize {
+        self.last_hit_end.len()
+    }
+}
+
+/// True if the defender could have escaped the combo on this frame, which ends it if they stay free for long enough.
+fn defender_actionable(frame: &slp_parser::Frame) -> bool {
+    use slp_parser::{BroadState, StandardBroadState};
+
+    matches!(
+        frame.state.broad_state(),
+        BroadState::Standard(StandardBroadState::Attack | StandardBroadState::GenericInactionable) | BroadState::Special(_)
+    ) || matches!(
+        frame.state.broad_state(),
+        BroadState::Standard(s) if s.is_actionable()
+    )
+}
+
+/// The thresholds `combo_start` uses, derived from `Config::strictness`.
+///
+/// Each is a straight line between its value at strictness 0 (lenient) and strictness 1 (strict), rounded:
+///
+/// | field | measures | 0.0 | 1.0 |
+/// |---|---|---|---|
+/// | `max_defender_consecutive_actionable` | frames in a row the defender may be free to act before the combo is considered dropped | 35 | 25 |
+/// | `max_attacker_total_hitstun` | frames the attacker may spend in hitstun, in total, during the combo (ignored by `ComboBreakerMode::Truncate`) | 65 | 55 |
+/// | `max_attacker_consecutive_grab_count` | grabs in a row, without an attack between, before the combo is rejected as a chaingrab | 6 | 2 |
+/// | `min_attacker_attack_actions` | attacks or specials the attacker must start during the combo | 3 | 9 |
+/// | `min_defender_total_damage` | percent dealt from the first hit to the kill | 20 | 60 |
+///
+/// The endpoints were picked by hand rather than fitted to data. They are set so that strictness 0 keeps
+/// nearly anything that ends in a kill, and 1 only keeps long, damaging, varied combos with little slack between hits.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ComboHeuristics {
+    pub max_defender_consecutive_actionable: usize,
+    pub max_attacker_total_hitstun: usize,
+    pub max_attacker_consecutive_grab_count: usize,
+    pub min_attacker_attack_actions: usize,
+    pub min_defender_total_damage: f32,
+}
+
+impl ComboHeuristics {
+    pub fn from_strictness(strictness: f32) -> Self {
+        ComboHeuristics {
+            max_defender_consecutive_actionable: (35.0 - 10.0 * strictness).round() as usize,
+            max_attacker_total_hitstun:          (65.0 - 10.0 * strictness).round() as usize,
+            max_attacker_consecutive_grab_count: ( 6.0 -  4.0 * strictness).round() as usize,
+            min_attacker_attack_actions:         ( 3.0 +  6.0 * strictness).round() as usize,
+            min_defender_total_damage:           (20.0 + 40.0 * strictness).round(),
+        }
+    }
+
+    /// The thresholds for `strictness`, one per line, to see what a strictness value means in practice.
+    pub fn debug_values(strictness: f32) -> String {
+        let h = ComboHeuristics::from_strictness(strictness);
+        format!(
+            "strictness {}:\n\
+            \x20 defender may be actionable for {} frames in a row\n\
+            \x20 attacker may be in hitstun for {} frames in total\n\
+            \x20 combo is rejected after {} grabs in a row\n\
+            \x20 attacker must use at least {} attacks\n\
+            \x20 defender must take at least {}%",
+            strictness,
+            h.max_defender_consecutive_actionable,
+            h.max_attacker_total_hitstun,
+            h.max_attacker_consecutive_grab_count,
+            h.min_attacker_attack_actions,
+            h.min_defender_total_damage,
+        )
+    }
+}
+
+/// Finds the first frame of a combo that lasts until the last of these frames, e.g. the frame before a death,
+/// using the same heuristics as a scan. `None` if there is no such combo, or the slices differ in length.
+///
+/// Scans build the lookups this needs once per game, this builds them on every call.
+pub fn find_combo_start(atk_frames: &[slp_parser::Frame], def_frames: &[slp_parser::Frame], config: &Config) -> Option<usize> {
+    let lookup = ComboLookup::new(atk_frames, def_frames, config);
+    combo_start(atk_frames, def_frames, &lookup, config)
 }
 
 /// Given a list of frames, tries to find a good place to start a combo which lasts till the end of the list.
@@ -50,85 +1391,107 @@ fn combo_start(
     // must be same len
     atk_frame: &[slp_parser::Frame],
     def_frame: &[slp_parser::Frame],
+    // built from frames these slices are a prefix of
+    lookup: &ComboLookup,
 
-    strictness: f32, 
+    config: &Config,
 ) -> Option<usize> {
-    let max_defender_consecutive_actionable = (35.0 - 10.0 * strictness).round() as usize;
-    let max_attacker_total_hitstun          = (65.0 - 10.0 * strictness).round() as usize;
-    let max_attacker_consecutive_grab_count = ( 6.0 -  4.0 * strictness).round() as usize;
-    let min_attacker_attack_actions         = ( 3.0 +  6.0 * strictness).round() as usize;
-    let min_defender_total_damage           = (20.0 + 40.0 * strictness).round();
-
-    use slp_parser::{ActionState, StandardActionState, BroadState, StandardBroadState};
+    let ComboHeuristics {
+        max_defender_consecutive_actionable: _,
+        max_attacker_total_hitstun,
+        max_attacker_consecutive_grab_count,
+        min_attacker_attack_actions,
+        min_defender_total_damage,
+    } = ComboHeuristics::from_strictness(config.strictness);
+    let min_attacker_attack_actions = config.min_attacker_attacks.unwrap_or(min_attacker_attack_actions);
+
+    use slp_parser::{BroadState, StandardBroadState};
+
+    // every index below is at most `last_hit_end`, so this keeps short games and mismatched slices from panicking
+    if atk_frame.len() != def_frame.len() || def_frame.is_empty() || def_frame.len() > lookup.len() {
+        trace!(frames = def_frame.len(), lookup = lookup.len(), "frames too short");
+        return None;
+    }
 
     // first pass ----------
     // determines potential start of combo
 
-    let mut last_hit_end = None;
-    for f in (0..atk_frame.len()).rev() {
-        let defender_state = def_frame[f].state.broad_state();
-        match defender_state {
-            BroadState::Standard(StandardBroadState::Hitstun | StandardBroadState::Ground | StandardBroadState::Attack) => {
-                last_hit_end = Some(f);
-                break;
-            }
-            _ => (),
+    let last_hit_end = match lookup.last_hit_end[def_frame.len() - 1] {
+        Some(f) => f,
+        None => {
+            trace!("defender was never hit");
+            return None;
         }
+    };
+
+    // a hit ending on the first frame leaves no frames for a combo to build up in
+    if last_hit_end == 0 {
+        trace!("last hit ended on the first frame");
+        return None;
     }
-    let last_hit_end = last_hit_end?;
 
-    let mut defender_consecutive_actionable = max_defender_consecutive_actionable;
-    let mut attacker_total_hitstun = max_attacker_total_hitstun;
-    let mut first_hit = None;
+    // Walking back from the frame before `last_hit_end`, the combo is cut off by whichever comes first:
+    // a combo breaker when truncating, the attacker's hitstun budget running out, or the defender staying
+    // actionable for too long. Each is looked up rather than walked to.
+    let last = last_hit_end - 1;
 
-    for f in (0..last_hit_end).rev() {
-        let attacker_state = atk_frame[f].state.broad_state();
-        let defender_state = def_frame[f].state.broad_state();
+    let breaker = lookup.last_attacker_hitstun[last];
 
-        match defender_state {
-            BroadState::Standard(StandardBroadState::Hitstun) => first_hit = Some(f),
-            _ => (),
-        }
-        
-        if matches!(
-            defender_state, 
-            BroadState::Standard(StandardBroadState::Attack | StandardBroadState::GenericInactionable) | BroadState::Special(_) 
-        ) || matches!(
-            defender_state, 
-            BroadState::Standard(s) if s.is_actionable()
-        ) {
-            defender_consecutive_actionable -= 1;
-        } else {
-            defender_consecutive_actionable = max_defender_consecutive_actionable;
-        }
+    // the frame the budget runs out on is still part of the combo
+    let hitstun_in_combo = lookup.attacker_hitstun_count[last];
+    let hitstun_cutoff = match max_attacker_total_hitstun {
+        0 => Some(last),
+        max if hitstun_in_combo >= max => Some(lookup.attacker_hitstun_frames[hitstun_in_combo - max]),
+        _ => None,
+    };
+
+    // as is the first frame of the defender's escape
+    let escape_cutoff = lookup.last_defender_escape[last]
+        .map(|end| end + 1 - lookup.max_defender_consecutive_actionable.max(1));
 
-        match attacker_state {
-            BroadState::Standard(StandardBroadState::Hitstun) => attacker_total_hitstun -= 1,
-            _ => (),
+    let combo_from = match (config.combo_breaker, breaker) {
+        // any hitstun is a breaker, so it comes before the hitstun budget runs out
+        (Some(ComboBreakerMode::Truncate), Some(breaker)) => {
+            trace!(frame = breaker, "combo breaker, truncating");
+            (breaker + 1).max(escape_cutoff.unwrap_or(0))
         }
+        _ => hitstun_cutoff.unwrap_or(0).max(escape_cutoff.unwrap_or(0)),
+    };
+    trace!(combo_from, ?hitstun_cutoff, ?escape_cutoff, "combo cut off");
 
-        if attacker_total_hitstun == 0 { break }
-        if defender_consecutive_actionable == 0 { break }
-    }
+    let first_hit = lookup.next_defender_hitstun[combo_from].filter(|&f| f <= last);
+    let last_breaker = breaker.filter(|&f| f >= combo_from);
 
     // pruning passes ---------
     // various more complicated checks
 
+    if config.combo_breaker == Some(ComboBreakerMode::Reject) {
+        if let (Some(first), Some(breaker)) = (first_hit, last_breaker) {
+            if first <= breaker {
+                trace!(first, breaker, "rejected, combo breaker");
+                return None;
+            }
+        }
+    }
+
     if let Some(first) = first_hit {
         // defender
-        let damage_dealt = def_frame.last().unwrap().percent - def_frame[first-1].percent;
-        if damage_dealt < min_defender_total_damage { return None; }
+        // first is 0 if the defender was in hitstun on the first frame of the replay
+        let damage_dealt = def_frame.last().unwrap().percent - def_frame[first.saturating_sub(1)].percent;
+        if damage_dealt < min_defender_total_damage {
+            trace!(damage_dealt, min_defender_total_damage, "rejected, too little damage");
+            return None;
+        }
 
         // attacker
         let mut attacker_consecutive_grabs = max_attacker_consecutive_grab_count;
         let mut attacker_attacks = 0;
+        let mut distinct_moves = Vec::new();
         for f in atk_frame[first..last_hit_end].iter() {
             // advance grab counter
-            if (
-                f.state == ActionState::Standard(StandardActionState::Catch)
-                || f.state == ActionState::Standard(StandardActionState::CatchDash)
-            ) && f.anim_frame == 0.0 {
-                attacker_consecutive_grabs -= 1;
+            // saturating, as the count starts at 0 for a strictness above 1
+            if fresh_grab(f) {
+                attacker_consecutive_grabs = attacker_consecutive_grabs.saturating_sub(1);
             }
 
             // reset grab counter on attack or special, and advance attack counter
@@ -140,183 +1503,1039 @@ fn combo_start(
 
                 if f.anim_frame == 1.0 {
                     attacker_attacks += 1;
+                    if !distinct_moves.contains(&f.state) { distinct_moves.push(f.state); }
                 }
             }
 
-            if attacker_consecutive_grabs == 0 { return None; }
+            if attacker_consecutive_grabs == 0 {
+                trace!("rejected, too many consecutive grabs");
+                return None;
+            }
+        }
+
+        if attacker_attacks < min_attacker_attack_actions {
+            trace!(attacker_attacks, min_attacker_attack_actions, "rejected, too few attacks");
+            return None;
+        }
+
+        if config.min_distinct_moves.is_some_and(|min| distinct_moves.len() < min) {
+            trace!(distinct_moves = distinct_moves.len(), "rejected, too few distinct moves");
+            return None;
+        }
+
+        if let Some(max_gap) = config.max_hit_gap_frames {
+            let mut last_hit = first;
+            for f in first+1..=last_hit_end {
+                if !hit_start(def_frame, f) { continue }
+
+                if f - last_hit > max_gap {
+                    trace!(gap = f - last_hit, max_gap, "rejected, too long between hits");
+                    return None;
+                }
+                last_hit = f;
+            }
+        }
+
+        if let Some(min_hits) = config.min_connected_hits {
+            let hits = connected_hits(def_frame, first, last_hit_end);
+            if hits < min_hits {
+                trace!(hits, min_hits, "rejected, too few hits");
+                return None;
+            }
         }
 
-        if attacker_attacks < min_attacker_attack_actions { return None; }
+        if let Some(max_ratio) = config.max_grab_ratio {
+            let ratio = grab_ratio(&def_frame[first.saturating_sub(1)..=last_hit_end]);
+            if ratio > max_ratio {
+                trace!(ratio, max_ratio, "rejected, too many throws");
+                return None;
+            }
+        }
     }
 
+    trace!(first_hit, last_hit_end, "combo_start decided");
     first_hit
 }
 
-/// If path is invalid or cannot be parsed, immediately returns zero.
+/// Minimum length of a window returned by `find_punish_windows`.
+pub const MIN_PUNISH_WINDOW_FRAMES: usize = 30;
+
+/// Finds every stretch of frames in which the attacker was punishing the defender, not only those ending in a kill,
+/// as `(start, end)` frame index pairs with `end` exclusive. Frames must be the same length.
+///
+/// A punish is a run of frames in which the defender was continuously in hitstun, grabbed or thrown, that lasts at
+/// least `MIN_PUNISH_WINDOW_FRAMES` and in which the attacker started at least one attack, special or grab.
+/// Unlike `combo_start`, no gaps between hits are allowed and strictness is not used.
+pub fn find_punish_windows(atk: &[slp_parser::Frame], def: &[slp_parser::Frame]) -> Vec<(usize, usize)> {
+    use slp_parser::{BroadState, StandardBroadState};
+
+    let offensive = |f: &slp_parser::Frame| fresh_grab(f) || (f.anim_frame == 1.0 && matches!(
+        f.state.broad_state(),
+        BroadState::Standard(StandardBroadState::Attack) | BroadState::Special(_)
+    ));
+
+    let len = atk.len().min(def.len());
+    let mut windows = Vec::new();
+    let mut f = 0;
+    while f < len {
+        if !held(&def[f]) {
+            f += 1;
+            continue;
+        }
+
+        let start = f;
+        while f < len && held(&def[f]) { f += 1; }
+
+        // the move that started the punish lands just before the defender is first hit
+        let attacked = atk[start.saturating_sub(STARTER_LOOKBACK)..f].iter().any(|a| offensive(a));
+        if f - start >= MIN_PUNISH_WINDOW_FRAMES && attacked {
+            windows.push((start, f));
+        }
+    }
+
+    windows
+}
+
+/// How many frames before the first hitstun frame to look for the move that caused it.
+const STARTER_LOOKBACK: usize = 10;
+
+/// The attacker's action state when they landed the hit that put the defender in hitstun on `first_hit`.
+fn combo_starter(atk_frame: &[slp_parser::Frame], first_hit: usize) -> Option<slp_parser::ActionState> {
+    use slp_parser::{ActionState, StandardActionState, BroadState, StandardBroadState};
+
+    if first_hit >= atk_frame.len() { return None }
+
+    (first_hit.saturating_sub(STARTER_LOOKBACK)..=first_hit).rev()
+        .map(|f| atk_frame[f].state)
+        .find(|&state| {
+            // a throw's hitstun comes from the throw, not the grab before it
+            matches!(state, ActionState::Standard(
+                StandardActionState::ThrowF | StandardActionState::ThrowB
+                | StandardActionState::ThrowHi | StandardActionState::ThrowLw
+            )) || matches!(
+                state.broad_state(),
+                BroadState::Standard(StandardBroadState::Attack) | BroadState::Special(_)
+            )
+        })
+}
+
+/// True if a hit connected on frame `f`: the defender entered hitstun or a thrown state,
+/// or took damage while already in one, as from each hit of a multi-hit move. `f` must be at least 1.
+fn hit_start(def_frame: &[slp_parser::Frame], f: usize) -> bool {
+    use slp_parser::{ActionState, StandardActionState, BroadState, StandardBroadState};
+
+    let being_hit = |f: &slp_parser::Frame| matches!(
+        f.state,
+        ActionState::Standard(
+            StandardActionState::ThrownF | StandardActionState::ThrownB
+            | StandardActionState::ThrownHi | StandardActionState::ThrownLw
+        )
+    ) || f.state.broad_state() == BroadState::Standard(StandardBroadState::Hitstun);
+
+    being_hit(&def_frame[f]) && (!being_hit(&def_frame[f-1]) || def_frame[f].percent > def_frame[f-1].percent)
+}
+
+/// Number of hits from the first hit on `first` to `last`, inclusive.
+fn connected_hits(def_frame: &[slp_parser::Frame], first: usize, last: usize) -> usize {
+    // the first hit may be on frame 0, where `hit_start` cannot look back
+    1 + (first+1..=last).filter(|&f| hit_start(def_frame, f)).count()
+}
+
+/// The first frame of a standing or dash grab, so that each grab is counted once.
+fn fresh_grab(f: &slp_parser::Frame) -> bool {
+    use slp_parser::{ActionState, StandardActionState};
+
+    matches!(f.state, ActionState::Standard(StandardActionState::Catch | StandardActionState::CatchDash))
+        && f.anim_frame == 0.0
+}
+
+/// Fraction of the frames on which the defender took damage that they took it from a throw.
+/// Pummels count as hits, but not as throws. 0 if the defender never took damage.
+fn grab_ratio(def_frame: &[slp_parser::Frame]) -> f32 {
+    use slp_parser::{ActionState, StandardActionState};
+
+    let mut hits = 0;
+    let mut throws = 0;
+    for w in def_frame.windows(2) {
+        if w[1].percent <= w[0].percent { continue }
+
+        hits += 1;
+        if matches!(w[1].state, ActionState::Standard(
+            StandardActionState::ThrownF | StandardActionState::ThrownB
+            | StandardActionState::ThrownHi | StandardActionState::ThrownLw
+        )) {
+            throws += 1;
+        }
+    }
+
+    if hits == 0 { 0.0 } else { throws as f32 / hits as f32 }
+}
+
+/// True if the attacker never took damage, was never in hitstun and, unless `allow_shield_hits`,
+/// was never in shieldstun in these frames.
+fn flawless(atk_frame: &[slp_parser::Frame], allow_shield_hits: bool) -> bool {
+    use slp_parser::{ActionState, StandardActionState, BroadState, StandardBroadState};
+
+    let took_damage = atk_frame.windows(2).any(|w| w[1].percent > w[0].percent);
+    let hitstun = atk_frame.iter().any(|f| f.state.broad_state() == BroadState::Standard(StandardBroadState::Hitstun));
+    let shield_hit = atk_frame.iter().any(|f| f.state == ActionState::Standard(StandardActionState::GuardSetOff));
+
+    !took_damage && !hitstun && (allow_shield_hits || !shield_hit)
+}
+
+/// True if the attacker was shielding or in shieldstun in the `window` frames up to `first_hit`.
+/// Reads frames before the combo, which `combo_start` never looks at.
+fn shielded_before(atk_frame: &[slp_parser::Frame], first_hit: usize, window: usize) -> bool {
+    use slp_parser::{ActionState, StandardActionState};
+
+    (first_hit.saturating_sub(window)..=first_hit).any(|f| matches!(
+        atk_frame[f].state,
+        ActionState::Standard(
+            StandardActionState::GuardOn | StandardActionState::Guard | StandardActionState::GuardOff
+            | StandardActionState::GuardSetOff | StandardActionState::GuardReflect
+        )
+    ))
+}
+
+/// True if the defender could not act on any frame from `first_hit` to the last hit in `def_frame`:
+/// they were in hitstun, grabbed, thrown or teching. Tumble counts as actionable if `tumble_breaks`.
+fn is_true_combo(def_frame: &[slp_parser::Frame], first_hit: usize, tumble_breaks: bool) -> bool {
+    use slp_parser::{ActionState, StandardActionState::*};
+
+    let last = match last_hit(def_frame) {
+        Some(f) if f >= first_hit => f,
+        _ => return false,
+    };
+
+    def_frame[first_hit..=last].iter().all(|f| match f.state {
+        ActionState::Standard(DamageFall) => !tumble_breaks,
+        ActionState::Standard(Passive | PassiveStandF | PassiveStandB | PassiveWall | PassiveWallJump | PassiveCeil) => true,
+        _ => held(f),
+    })
+}
+
+/// True if the player is in hitstun, grabbed or being thrown.
+fn held(f: &slp_parser::Frame) -> bool {
+    use slp_parser::{ActionState, StandardActionState::*, BroadState, StandardBroadState};
+
+    match f.state {
+        ActionState::Standard(
+            CapturePulledHi | CaptureWaitHi | CaptureDamageHi | CapturePulledLw | CaptureWaitLw | CaptureDamageLw
+            | ThrownF | ThrownB | ThrownHi | ThrownLw
+        ) => true,
+        state => state.broad_state() == BroadState::Standard(StandardBroadState::Hitstun),
+    }
+}
+
+/// How many frames after the first hit a tech opener may continue, e.g. the wavedash after a shine.
+const TECH_WINDOW: usize = 30;
+
+/// Looks for a waveshine or wavedash in the attacker's states around `first_hit`.
+/// `atk_frame` ends at the kill, so the window never runs past it.
+fn combo_tech(atk_frame: &[slp_parser::Frame], first_hit: usize) -> Option<ComboTech> {
+    use slp_parser::{ActionState, StandardActionState, BroadState, Character};
+
+    let window = &atk_frame[first_hit.saturating_sub(STARTER_LOOKBACK)..(first_hit + TECH_WINDOW).min(atk_frame.len())];
+    let shine_character = matches!(window.first()?.character, Character::Fox | Character::Falco);
+
+    // each state once, in order
+    let mut states = Vec::new();
+    for f in window {
+        if states.last() != Some(&f.state) { states.push(f.state) }
+    }
+
+    let is = |state: ActionState, s: StandardActionState| state == ActionState::Standard(s);
+    let airdodge = |state| is(state, StandardActionState::EscapeAir) || is(state, StandardActionState::LandingFallSpecial);
+
+    // shine is the only special fox and falco can jump out of
+    let waveshine = shine_character && states.windows(3).any(|w| {
+        matches!(w[0].broad_state(), BroadState::Special(_)) && is(w[1], StandardActionState::KneeBend) && airdodge(w[2])
+    });
+    if waveshine { return Some(ComboTech::Waveshine) }
+
+    let wavedash = states.windows(2).any(|w| is(w[0], StandardActionState::KneeBend) && airdodge(w[1]));
+    if wavedash { return Some(ComboTech::Wavedash) }
+
+    None
+}
+
+/// The last frame on which the defender took damage, i.e. the frame the killing move connected.
+fn last_hit(def_frame: &[slp_parser::Frame]) -> Option<usize> {
+    (1..def_frame.len()).rev().find(|&f| def_frame[f].percent > def_frame[f-1].percent)
+}
+
+/// True if the defender's stock count dropped across the dead frames `death..respawn`,
+/// so that a `Dead` broad state that does not cost a stock is not counted as a kill.
+fn took_stock(def_frame: &[slp_parser::Frame], death: usize, respawn: usize) -> bool {
+    if death == 0 { return false }
+    // the replay may end before the stock count is updated
+    if respawn == def_frame.len() { return true }
+    // stock count may only be decremented partway through the dead frames
+    def_frame[respawn - 1].stock_count < def_frame[death - 1].stock_count
+}
+
+/// The `(death, respawn)` frames of each stock the player lost, where `death` is the first `Dead` frame and
+/// `respawn` the first frame after it that is not, or the frame count if the replay ends first.
+fn stock_losses(frames: &[slp_parser::Frame]) -> Vec<(usize, usize)> {
+    let dead = |f: &slp_parser::Frame| f.state.broad_state() == slp_parser::StandardBroadState::Dead.into();
+
+    let mut losses = Vec::new();
+    let mut f = 0;
+    while f < frames.len() {
+        if !dead(&frames[f]) {
+            f += 1;
+            continue;
+        }
+
+        let mut respawn = f + 1;
+        while respawn < frames.len() && dead(&frames[respawn]) { respawn += 1; }
+
+        if took_stock(frames, f, respawn) { losses.push((f, respawn)); }
+        f = respawn;
+    }
+
+    losses
+}
+
+/// True if the player's final stock was taken, ending the game.
+fn lost_last_stock(frames: &[slp_parser::Frame]) -> bool {
+    let dead = |f: &slp_parser::Frame| f.state.broad_state() == slp_parser::StandardBroadState::Dead.into();
+
+    let mut death = frames.len();
+    while death > 0 && dead(&frames[death-1]) { death -= 1; }
+
+    // stock count may already be decremented on the death frame
+    death < frames.len() && death > 0 && frames[death-1].stock_count <= 1
+}
+
+/// All damage the player took over the game. Percent drops back to 0 on each death, so only increases are summed.
+fn total_damage(frames: &[slp_parser::Frame]) -> f32 {
+    frames.windows(2).map(|w| (w[1].percent - w[0].percent).max(0.0)).sum()
+}
+
+/// Results shared between scanning threads.
+#[derive(Default)]
+struct ScanOutput {
+    combos: Vec<Combo>,
+    stats: ScanStats,
+    checkpoint: Option<checkpoint::Checkpoint>,
+}
+
+enum FileOutcome {
+    Analyzed(Vec<Combo>),
+    /// Players did not pass the config's filters.
+    Skipped,
+    /// Older than `Config::min_version`.
+    TooOld,
+    /// The replay does not have exactly two players.
+    NotTwoPlayer,
+    /// `read_info` failed.
+    ParseFailed,
+    /// `read_info` or `read_game` failed on a compressed replay.
+    DecompressionFailed,
+    /// One of the players has no frames.
+    MissingFrames,
+    /// `read_info` succeeded and the players passed the filters, but `read_game` failed,
+    /// which usually means the frame data is truncated or corrupt.
+    GameParseFailed,
+}
+
+// Progress events are sent with `let _ =`, since the receiver may be dropped partway through a scan
+// (e.g. a closed UI), and that should not stop the scan.
 fn combos(
     config: &Config,
     path: &Path,
-    combos: &std::sync::Mutex<&mut Vec<Combo>>,
+    output: &std::sync::Mutex<&mut ScanOutput>,
+    sender: Option<&std::sync::mpsc::Sender<ProgressEvent>>,
+    progress: Option<&ScanProgress>,
 ) -> usize {
+    log::info!("Processing: {:?}", path);
+
+    #[cfg(feature = "tracing")]
+    let span = tracing::span!(tracing::Level::INFO, "process_file", path = ?path, combos = tracing::field::Empty);
+    #[cfg(feature = "tracing")]
+    let _entered = span.enter();
+
+    let mut warnings = Vec::new();
+    let outcome = scan_file(config, path, sender, &mut warnings);
+
+    #[cfg(feature = "tracing")]
+    if let FileOutcome::Analyzed(ref file_combos) = outcome { span.record("combos", file_combos.len()); }
+
+    match outcome {
+        FileOutcome::Analyzed(ref file_combos) => log::debug!("Found {} combos in {:?}", file_combos.len(), path),
+        FileOutcome::Skipped => log::debug!("Skipped {:?}, players do not match the filters", path),
+        FileOutcome::TooOld => log::debug!("Skipped {:?}, replay is older than the minimum version", path),
+        FileOutcome::NotTwoPlayer | FileOutcome::MissingFrames | FileOutcome::ParseFailed
+        | FileOutcome::DecompressionFailed | FileOutcome::GameParseFailed => (), // warned in scan_file
+    }
+
+    if let Some(sender) = sender {
+        if let FileOutcome::ParseFailed | FileOutcome::DecompressionFailed | FileOutcome::GameParseFailed = outcome {
+            let _ = sender.send(ProgressEvent::ParseFailed(path.to_path_buf()));
+        }
+        let _ = sender.send(ProgressEvent::FileScanned);
+    }
+
+    if let Some(progress) = progress {
+        use std::sync::atomic::Ordering;
+        if let FileOutcome::Analyzed(ref file_combos) = outcome {
+            progress.combos_found.fetch_add(file_combos.len(), Ordering::Relaxed);
+        }
+        progress.files_scanned.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let mut output = output.lock().unwrap();
+    output.stats.warnings.extend(warnings);
+    if let Some(ref mut checkpoint) = output.checkpoint {
+        let file_combos = match outcome {
+            FileOutcome::Analyzed(ref c) => &c[..],
+            FileOutcome::Skipped | FileOutcome::TooOld | FileOutcome::NotTwoPlayer | FileOutcome::MissingFrames
+            | FileOutcome::ParseFailed | FileOutcome::DecompressionFailed | FileOutcome::GameParseFailed => &[],
+        };
+        checkpoint.record(path, file_combos);
+    }
+
+    match outcome {
+        FileOutcome::Analyzed(file_combos) => {
+            let found = file_combos.len();
+            output.stats.files_analyzed += 1;
+            output.stats.combos_found += found;
+            output.combos.extend(file_combos);
+            return found;
+        }
+        FileOutcome::Skipped => output.stats.files_skipped += 1,
+        FileOutcome::TooOld => output.stats.files_too_old += 1,
+        FileOutcome::NotTwoPlayer => {
+            output.stats.files_skipped += 1;
+            output.stats.warnings.push(ScanWarning::NotTwoPlayer(path.to_path_buf()));
+        }
+        FileOutcome::MissingFrames => {
+            output.stats.files_skipped += 1;
+            output.stats.warnings.push(ScanWarning::MissingFrames(path.to_path_buf()));
+        }
+        FileOutcome::ParseFailed => output.stats.parse_failures += 1,
+        FileOutcome::DecompressionFailed => {
+            output.stats.parse_failures += 1;
+            output.stats.warnings.push(ScanWarning::DecompressionFailed(path.to_path_buf()));
+        }
+        FileOutcome::GameParseFailed => {
+            output.stats.parse_failures += 1;
+            output.stats.warnings.push(ScanWarning::GameParseFailed(path.to_path_buf()));
+        }
+    }
+    0
+}
+
+/// Finds the combos in a single replay, sorted by start frame.
+/// If path is invalid, cannot be parsed or is not a 2 player game, returns no combos.
+pub fn find_combos_in_file(config: &Config, path: &Path) -> Vec<Combo> {
+    match scan_file(config, path, None, &mut Vec::new()) {
+        FileOutcome::Analyzed(combos) => combos,
+        FileOutcome::Skipped | FileOutcome::TooOld | FileOutcome::NotTwoPlayer | FileOutcome::MissingFrames
+        | FileOutcome::ParseFailed | FileOutcome::DecompressionFailed | FileOutcome::GameParseFailed => Vec::new(),
+    }
+}
+
+/// Finds every stock lost in a replay, as `(port, frame)` pairs sorted by frame, where `frame` is the first frame
+/// the player was dead. Unlike `find_combos_in_file` this works for any number of players and uses no filters,
+/// e.g. for a timeline of deaths. If the replay cannot be parsed, returns no deaths.
+pub fn find_deaths(path: &Path) -> Vec<(usize, usize)> {
+    let game = match slp_parser::read_game(path) {
+        Ok((game, _)) => game,
+        Err(_) => {
+            log::warn!("Failed to read the frames of {:?}", path);
+            return Vec::new();
+        }
+    };
+
+    let mut deaths = game.frames.iter()
+        .enumerate()
+        .filter_map(|(port, frames)| Some((port, frames.as_ref()?)))
+        .flat_map(|(port, frames)| stock_losses(frames).into_iter().map(move |(death, _)| (port, death)))
+        .collect::<Vec<_>>();
+    deaths.sort_by_key(|&(port, frame)| (frame, port));
+    deaths
+}
+
+/// Finds the combos in a replay held in memory, e.g. one received over the network, sorted by start frame.
+/// `path` is only used for the returned combos' paths, and its extension for whether the replay is compressed.
+///
+/// slp_parser only reads replays from files, so the bytes are written to a new temporary file that is removed
+/// before returning, or if the scan panics. Errors are from creating or writing that file.
+pub fn combos_from_bytes(config: &Config, bytes: &[u8], path: &Path) -> std::io::Result<Vec<Combo>> {
+    use std::io::Write;
+
+    /// Removes the file when dropped.
+    struct TempFile(PathBuf);
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    static NEXT_ID: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("slp");
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+
+    // `create_new` fails rather than following a symlink or reusing a file someone else put there,
+    // so a name that is taken is skipped
+    let (temp, mut file) = loop {
+        let id = NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let name = format!("slp_combo_finder_{}_{}_{}.{}", std::process::id(), nanos, id, extension);
+        let temp_path = std::env::temp_dir().join(name);
+
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&temp_path) {
+            Ok(file) => break (TempFile(temp_path), file),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    };
+
+    file.write_all(bytes)?;
+    drop(file);
+
+    let mut combos = find_combos_in_file(config, &temp.0);
+    for c in combos.iter_mut() { c.path = path.to_path_buf(); }
+    Ok(combos)
+}
+
+/// Decodes the Shift JIS names and connect codes in `fields` into one string, returning it and the end of each
+/// field in it. Fields that are not valid Shift JIS keep their ascii characters, with the other bytes replaced,
+/// and `path` is recorded as a `ScanWarning::InvalidNames`.
+fn decode_names<const N: usize>(
+    path: &Path,
+    fields: [&[u8]; N],
+    warnings: &mut Vec<ScanWarning>,
+) -> (String, [usize; N]) {
+    let mut buf = String::with_capacity(128);
+    let mut names_valid = true;
+    let ends = fields.map(|bytes| {
+        let start = buf.len();
+        if slp_parser::decode_shift_jis(bytes, &mut buf).is_none() {
+            // keep the ascii characters, so that connect codes and most tags still match the filters
+            buf.truncate(start);
+            buf.extend(bytes.iter()
+                .take_while(|&&b| b != 0)
+                .map(|&b| if b.is_ascii() { b as char } else { char::REPLACEMENT_CHARACTER }));
+            names_valid = false;
+        }
+        buf.len()
+    });
+
+    if !names_valid {
+        log::warn!("Names in {:?} are not valid Shift JIS, non ascii characters were replaced", path);
+        warnings.push(ScanWarning::InvalidNames(path.to_path_buf()));
+    }
+
+    (buf, ends)
+}
+
+/// True if a combo from `start` to the defender's death on `death` matches the config's stock filters,
+/// and the attacker's percent and stock filters when the combo started.
+fn passes_stock_filters(
+    config: &Config,
+    atk_frame: &[slp_parser::Frame],
+    def_frame: &[slp_parser::Frame],
+    start: usize,
+    death: usize,
+) -> bool {
+    // stock count may already be decremented on the death frame
+    let defender_stocks = def_frame[death.saturating_sub(1)].stock_count as u8;
+    let attacker_stocks = atk_frame[death].stock_count as u8;
+    if config.max_defender_stocks.is_some_and(|max| defender_stocks > max) { return false }
+    if config.max_attacker_stocks.is_some_and(|max| attacker_stocks > max) { return false }
+
+    // the attacker's state when the combo started
+    let attacker_start = &atk_frame[start];
+    if config.min_attacker_percent.is_some_and(|min| attacker_start.percent < min) { return false }
+    if config.attacker_last_stock && attacker_start.stock_count > 1 { return false }
+
+    true
+}
+
+/// True if the players match the config's player and opponent filters, with `p` as the attacker.
+fn passes(
+    config: &Config,
+    p_char: slp_parser::Character,
+    p_code: &str,
+    p_name: &str,
+    o_char: slp_parser::Character,
+    o_code: &str,
+    o_name: &str,
+) -> bool {
+    // these are the starting characters, so a Zelda may transform into a Sheik that is allowed,
+    // and each kill is checked again against the character that made it in `inner`
+    let allows = |allowed: fn(&Config, slp_parser::Character) -> bool, c| {
+        allowed(config, c) || transform_partner(c).is_some_and(|t| allowed(config, t))
+    };
+    if !allows(Config::allows_player_character, p_char) { return false }
+    if !allows(Config::allows_opponent_character, o_char) { return false }
+    if config.player_name       .as_ref().is_some_and(|c| !p_name.contains(c)) { return false }
+    if config.opponent_name     .as_ref().is_some_and(|c| !o_name.contains(c)) { return false }
+    if config.player_code       .as_ref().is_some_and(|c| !p_code.contains(c)) { return false }
+    if config.opponent_code     .as_ref().is_some_and(|c| !o_code.contains(c)) { return false }
+    if config.either_player_code.as_ref().is_some_and(|c| !p_code.contains(c) && !o_code.contains(c)) { return false }
+
+    true
+}
+
+/// One side of a 2 player game, as read from the game info.
+struct Player<'a> {
+    character: slp_parser::Character,
+    name: &'a str,
+    code: &'a str,
+    /// Slippi port number, from 1 to 4.
+    port: usize,
+}
+
+/// The metadata shared by every combo `atk` lands on `def` in a game, which `inner` fills in per combo.
+fn players_template(atk: &Player, def: &Player) -> ComboMetadata {
+    ComboMetadata {
+        attacker_character: atk.character,
+        defender_character: def.character,
+        attacker_name: atk.name.to_string(),
+        attacker_code: atk.code.to_string(),
+        defender_name: def.name.to_string(),
+        defender_code: def.code.to_string(),
+        attacker_port: atk.port,
+        defender_port: def.port,
+        start_percent: 0.0,
+        end_percent: 0.0,
+        raw_start: FrameIndex(0),
+        raw_end: FrameIndex(0),
+        opener_move: None,
+        opener: None,
+        kill_move_category: None,
+        ender: None,
+        kill_direction: None,
+        grab_ratio: 0.0,
+        grab_count: 0,
+        hit_count: 0,
+        tech: None,
+        is_true_combo: false,
+    }
+}
+
+fn scan_file(
+    config: &Config,
+    path: &Path,
+    sender: Option<&std::sync::mpsc::Sender<ProgressEvent>>,
+    // problems with files that are still scanned
+    warnings: &mut Vec<ScanWarning>,
+) -> FileOutcome {
     fn inner<'a>(
         atk_frame: &[slp_parser::Frame],
         def_frame: &[slp_parser::Frame],
 
         config: &Config,
         path: &Path,
-        combos: &std::sync::Mutex<&mut Vec<Combo>>,
-        found: &mut usize,
+        sender: Option<&std::sync::mpsc::Sender<ProgressEvent>>,
+        // names and codes, the rest is filled in per combo
+        players: &ComboMetadata,
+        file_combos: &mut Vec<Combo>,
     ) {
-        let frame_count = atk_frame.len();
-
-        let mut f = 0;
-        while f < frame_count {
-            if def_frame[f].state.broad_state() != slp_parser::StandardBroadState::Dead.into() { 
-                f += 1;
-                continue;
-            }
+        // the callers pass equal lengths, this keeps the paired indexing in bounds regardless
+        let frame_count = atk_frame.len().min(def_frame.len());
+        let lookup = ComboLookup::new(atk_frame, def_frame, config);
 
+        for (f, respawn) in stock_losses(&def_frame[..frame_count]) {
             loop {
-                // second character check to make sure it's not transformed sheik/zelda
-                if config.player_character.is_some_and(|c| c != atk_frame[f].character) { break; }
-                if config.opponent_character.is_some_and(|c| c != def_frame[f].character) { break; }
+                // second character check for transformed sheik/zelda, since `passes` allows either
+                if !config.allows_player_character(atk_frame[f].character) { break; }
+                if !config.allows_opponent_character(def_frame[f].character) { break; }
 
                 if let Some(kill_combo_start) = combo_start(
                     &atk_frame[..f],
                     &def_frame[..f],
-                    config.strictness,
+                    &lookup,
+                    config,
                 ) {
-                    let start = kill_combo_start.saturating_sub(config.lead_in);
-                    combos.lock().unwrap().push(Combo {
+                    let opener_move = combo_starter(atk_frame, kill_combo_start);
+                    if let Some(ref moves) = config.starter_move {
+                        match opener_move {
+                            Some(starter) if moves.contains(&starter) => (),
+                            _ => break,
+                        }
+                    }
+                    let opener = opener_move.map(ComboOpener::from_action_state);
+                    if config.opener.is_some_and(|o| opener != Some(o)) { break }
+
+                    let kill_hit = last_hit(&def_frame[..f]);
+                    let ender = kill_hit.and_then(|hit| classify_ender(atk_frame, def_frame, hit));
+                    if config.ender.is_some_and(|e| ender != Some(e)) { break }
+
+                    if config.flawless && !flawless(&atk_frame[kill_combo_start.saturating_sub(1)..f], config.flawless_allow_shield_hits) {
+                        break
+                    }
+                    if let Some(max) = config.max_attacker_damage {
+                        if total_damage(&atk_frame[kill_combo_start.saturating_sub(1)..f]) > max { break }
+                    }
+
+                    let true_combo = is_true_combo(&def_frame[..f], kill_combo_start, config.tumble_breaks_true_combo);
+                    if config.true_combos_only && !true_combo { break }
+
+                    if let Some(window) = config.out_of_shield_frames {
+                        if !shielded_before(atk_frame, kill_combo_start, window) { break }
+                    }
+
+                    let attacker = atk_frame[f].character;
+                    let lead_in = config.effective_lead_in(attacker);
+                    let lead_out = config.effective_lead_out(attacker);
+
+                    let start = kill_combo_start.saturating_sub(lead_in);
+
+                    if !passes_stock_filters(config, atk_frame, def_frame, kill_combo_start, f) { break; }
+
+                    // stock count may already be decremented on the death frame
+                    let last_stock = f > 0 && def_frame[f - 1].stock_count <= 1;
+                    let game_ending = last_stock || respawn == frame_count;
+
+                    let metadata = ComboMetadata {
+                        attacker_character: attacker,
+                        defender_character: def_frame[f].character,
+                        start_percent: def_frame[kill_combo_start.saturating_sub(1)].percent,
+                        end_percent: def_frame[f-1].percent,
+                        raw_start: FrameIndex(kill_combo_start),
+                        raw_end: FrameIndex(f),
+                        opener_move,
+                        opener,
+                        kill_move_category: kill_hit.map(|hit| KillMoveCategory::from_action_state(atk_frame[hit].state)),
+                        ender,
+                        kill_direction: KillDirection::from_action_state(def_frame[f].state),
+                        grab_ratio: grab_ratio(&def_frame[kill_combo_start.saturating_sub(1)..f]),
+                        grab_count: atk_frame[kill_combo_start..f].iter().filter(|frame| fresh_grab(frame)).count(),
+                        hit_count: connected_hits(def_frame, kill_combo_start, f - 1),
+                        is_true_combo: true_combo,
+                        tech: if config.detect_tech { combo_tech(&atk_frame[..f], kill_combo_start) } else { None },
+                        ..players.clone()
+                    };
+
+                    let combo = Combo {
                         path: path.to_path_buf(), 
-                        start,
-                        end: (f+config.lead_out).min(frame_count),
-                    });
-                    *found += 1
+                        start: FrameIndex(start),
+                        end: FrameIndex((f+lead_out).min(frame_count)),
+                        game_ending,
+                        metadata: Some(metadata),
+                    };
+                    if let Some(sender) = sender { let _ = sender.send(ProgressEvent::ComboFound(combo.clone())); }
+                    file_combos.push(combo);
                 }
 
                 break;
             }
-
-            f += 1;
-            while f < frame_count && def_frame[f].state.broad_state() == slp_parser::StandardBroadState::Dead.into() { f += 1; }
         }
     }
 
-    fn passes(
-        config: &Config,
-        p_char: slp_parser::Character,
-        p_code: &str,
-        p_name: &str,
-        o_char: slp_parser::Character,
-        o_code: &str,
-        o_name: &str,
-    ) -> bool {
-        if config.player_character  .is_some_and(|c| c != p_char) { return false }
-        if config.opponent_character.is_some_and(|c| c != o_char) { return false }
-        if config.player_name       .as_ref().is_some_and(|c| !p_name.contains(c)) { return false }
-        if config.opponent_name     .as_ref().is_some_and(|c| !o_name.contains(c)) { return false }
-        if config.player_code       .as_ref().is_some_and(|c| !p_code.contains(c)) { return false }
-        if config.opponent_code     .as_ref().is_some_and(|c| !o_code.contains(c)) { return false }
-
-        true
-    }
-
     let info = match slp_parser::read_info(path) {
         Ok(i) => i,
-        Err(_) => return 0,
+        Err(_) if is_compressed(path) => {
+            log::warn!("Failed to decompress {:?}", path);
+            return FileOutcome::DecompressionFailed;
+        }
+        Err(_) => {
+            log::warn!("Failed to read game info from {:?}", path);
+            if let Some(damage) = file_damage(path) { warnings.push(damage); }
+            return FileOutcome::ParseFailed;
+        }
     };
 
     let (low_port, high_port) = match info.low_high_ports() {
         Some(p) => p,
-        None => return 0,
+        None => {
+            log::warn!("Skipping {:?}: not a 2-player game", path);
+            return FileOutcome::NotTwoPlayer;
+        }
     };
 
-    let mut buf = String::with_capacity(128);
+    if config.match_type.is_some() || config.min_version.is_some() {
+        let game_start = game_start::read_game_start(path);
+
+        if let (Some(min), Some(g)) = (config.min_version, &game_start) {
+            if g.version < min { return FileOutcome::TooOld }
+        }
+
+        if let Some(match_type) = config.match_type {
+            let found = game_start
+                .and_then(|g| g.match_id)
+                .and_then(|id| MatchType::from_match_id(&id));
+            if found != Some(match_type) { return FileOutcome::Skipped }
+        }
+    }
+
+    // old or corrupt replays may be missing any of these, which should not take down the whole scan
+    let (Some(p1_colour), Some(p2_colour)) = (
+        info.starting_character_colours[low_port],
+        info.starting_character_colours[high_port],
+    ) else {
+        log::warn!("Failed to read the characters of {:?}", path);
+        return FileOutcome::ParseFailed;
+    };
+    let p1_char = p1_colour.character();
+    let p2_char = p2_colour.character();
+
+    let (buf, [p1_name_end, p2_name_end, p1_code_end, p2_code_end]) = decode_names(path, [
+        &info.names[low_port][..],
+        &info.names[high_port][..],
+        &info.connect_codes[low_port][..],
+        &info.connect_codes[high_port][..],
+    ], warnings);
 
-    let p1_char = info.starting_character_colours[low_port].unwrap().character();
-    let p2_char = info.starting_character_colours[high_port].unwrap().character();
-
-    slp_parser::decode_shift_jis(&info.names[low_port], &mut buf).unwrap();
-    let p1_name_end = buf.len();
-    slp_parser::decode_shift_jis(&info.names[high_port], &mut buf).unwrap();
-    let p2_name_end = buf.len();
-    slp_parser::decode_shift_jis(&info.connect_codes[low_port], &mut buf).unwrap();
-    let p1_code_end = buf.len();
-    slp_parser::decode_shift_jis(&info.connect_codes[high_port], &mut buf).unwrap();
-    let p2_code_end = buf.len();
-    
     let p1_name = &buf[0..p1_name_end];
     let p2_name = &buf[p1_name_end..p2_name_end];
     let p1_code = &buf[p2_name_end..p1_code_end];
     let p2_code = &buf[p1_code_end..p2_code_end];
 
-    let p1_passes = passes(config, p1_char, p1_code, p1_name, p2_char, p2_code, p2_name);
-    let p2_passes = passes(config, p2_char, p2_code, p2_name, p1_char, p1_code, p1_name);
+    let mut p1_passes = passes(config, p1_char, p1_code, p1_name, p2_char, p2_code, p2_name);
+    let mut p2_passes = passes(config, p2_char, p2_code, p2_name, p1_char, p1_code, p1_name);
     
-    let mut found = 0;
+    if !(p1_passes | p2_passes) { return FileOutcome::Skipped }
+
+    // slp_parser can only parse every port's frames at once, so both are parsed even when only one direction passes
+    let (game, _) = match slp_parser::read_game(path) {
+        Ok(g) => g,
+        Err(_) if is_compressed(path) => {
+            log::warn!("Failed to decompress {:?}", path);
+            return FileOutcome::DecompressionFailed;
+        }
+        Err(_) => {
+            // a truncated file has a readable game info, but not all of its frames
+            if let Some(damage) = file_damage(path) {
+                log::warn!("Failed to read the frames of {:?}", path);
+                warnings.push(damage);
+                return FileOutcome::ParseFailed;
+            }
+            log::warn!("Game info of {:?} matched the filters, but its frames could not be parsed", path);
+            return FileOutcome::GameParseFailed;
+        }
+    };
 
-    if p1_passes | p2_passes {
-        let (game, _) = match slp_parser::read_game(path) {
-            Ok(g) => g,
-            Err(_) => return 0,
-        };
+    let (Some(f1), Some(f2)) = (game.frames[low_port].as_ref(), game.frames[high_port].as_ref()) else {
+        log::warn!("Skipping {:?}: a player has no frames", path);
+        return FileOutcome::MissingFrames;
+    };
 
-        let f1 = game.frames[low_port].as_ref().unwrap();
-        let f2 = game.frames[high_port].as_ref().unwrap();
+    // frames are indexed in pairs by `inner` and `combo_start`
+    let frame_count = f1.len().min(f2.len());
+    if f1.len() != f2.len() {
+        log::warn!("Players in {:?} have {} and {} frames, scanning the first {}", path, f1.len(), f2.len(), frame_count);
+        warnings.push(ScanWarning::FrameCountMismatch(path.to_path_buf()));
+    }
+    let (f1, f2) = (&f1[..frame_count], &f2[..frame_count]);
+
+    // the game info does not record the game's length, so this needs the parsed frames
+    if config.min_game_duration_frames.is_some_and(|min| frame_count < min) { return FileOutcome::Skipped }
+    if config.max_game_duration_frames.is_some_and(|max| frame_count > max) { return FileOutcome::Skipped }
+
+    // e.g. a game quit during the countdown, too short to hold a combo and its lead in for either player
+    let min_lead_in = [f1, f2].iter()
+        .filter_map(|f| f.first())
+        .map(|f| config.effective_lead_in(f.character))
+        .min()
+        .unwrap_or(config.lead_in);
+    if frame_count <= min_lead_in {
+        log::debug!("{:?} has only {} frames", path, frame_count);
+        warnings.push(ScanWarning::TooShort(path.to_path_buf(), frame_count));
+        return FileOutcome::Analyzed(Vec::new());
+    }
 
-        if p1_passes {
-            inner(f1, f2, config, path, combos, &mut found)
-        }
+    let player_filtered = config.player_name.is_some() || config.player_code.is_some() || config.player_character.is_some()
+        || config.player_characters.is_some();
+    if config.player_won_only && player_filtered {
+        let p1_lost = lost_last_stock(f1);
+        let p2_lost = lost_last_stock(f2);
+        p1_passes &= p2_lost && !p1_lost;
+        p2_passes &= p1_lost && !p2_lost;
+    }
 
-        if p2_passes {
-            inner(f2, f1, config, path, combos, &mut found)
-        }
+    // checked before `inner` so that one sided games (e.g. an idle opponent) skip the per stock scan
+    if let Some(min) = config.min_total_game_damage {
+        p1_passes &= total_damage(f2) >= min;
+        p2_passes &= total_damage(f1) >= min;
     }
 
-    found
+    // collected per file so that both ports are sorted together and the shared list is locked once
+    let mut file_combos = Vec::new();
+
+    let p1 = Player { character: p1_char, name: p1_name, code: p1_code, port: low_port + 1 };
+    let p2 = Player { character: p2_char, name: p2_name, code: p2_code, port: high_port + 1 };
+
+    if p1_passes { inner(f1, f2, config, path, sender, &players_template(&p1, &p2), &mut file_combos) }
+    if p2_passes { inner(f2, f1, config, path, sender, &players_template(&p2, &p1), &mut file_combos) }
+
+    file_combos.sort_by_key(|c| c.start);
+    FileOutcome::Analyzed(file_combos)
 }
 
+/// Scans every replay in `path`, which may be a single replay, a single folder or a tree of folders.
+/// A file without a replay extension is a `TargetPathError::NotAReplay`. Combos are sorted by path, then start frame, so repeated scans give the same result.
+/// A folder without replays logs a warning and sends `ProgressEvent::NoFilesFound`, or is a
+/// `TargetPathError::NoFilesFound` if `Config::strict` is set.
+///
+/// On `wasm32` the scan always runs on a single thread, as `std::thread` is unavailable there.
+/// The combo list is still shared through a `std::sync::Mutex`, which works (uncontended) on wasm.
 pub fn target_path(
     config: &Config,
     path: &Path,
-    sender: Option<std::sync::mpsc::Sender<usize>>,
+    sender: Option<std::sync::mpsc::Sender<ProgressEvent>>,
 ) -> Result<Vec<Combo>, TargetPathError> {
-    if !matches!(path.try_exists(), Ok(true)) { return Err(TargetPathError::PathNotFound) }
+    target_path_with_stats(config, path, sender).map(|(combos, _)| combos)
+}
+
+/// Same as `target_path`, but also returns counts of what happened to each file and how long the scan took.
+pub fn target_path_with_stats(
+    config: &Config,
+    path: &Path,
+    sender: Option<std::sync::mpsc::Sender<ProgressEvent>>,
+) -> Result<(Vec<Combo>, ScanStats), TargetPathError> {
+    scan_path(config, path, sender.as_ref(), None)
+}
+
+/// Same as `target_path_with_stats`, but reports progress through counters the caller can poll
+/// from another thread instead of a channel.
+pub fn target_path_with_progress(
+    config: &Config,
+    path: &Path,
+    progress: &ScanProgress,
+) -> Result<(Vec<Combo>, ScanStats), TargetPathError> {
+    scan_path(config, path, None, Some(progress))
+}
+
+/// Scans exactly the given replay files, without walking any directories.
+pub fn target_paths(
+    config: &Config,
+    targets: &[PathBuf],
+    sender: Option<std::sync::mpsc::Sender<ProgressEvent>>,
+) -> Result<Vec<Combo>, TargetPathError> {
+    target_paths_with_stats(config, targets, sender).map(|(combos, _)| combos)
+}
+
+/// Same as `target_paths`, but also returns counts of what happened to each file.
+pub fn target_paths_with_stats(
+    config: &Config,
+    targets: &[PathBuf],
+    sender: Option<std::sync::mpsc::Sender<ProgressEvent>>,
+) -> Result<(Vec<Combo>, ScanStats), TargetPathError> {
+    Ok(scan_targets(config, targets, sender.as_ref(), None))
+}
+
+/// Same as `target_paths_with_stats`, but reports progress through counters instead of a channel.
+pub fn target_paths_with_progress(
+    config: &Config,
+    targets: &[PathBuf],
+    progress: &ScanProgress,
+) -> Result<(Vec<Combo>, ScanStats), TargetPathError> {
+    Ok(scan_targets(config, targets, None, Some(progress)))
+}
+
+/// Progress counters for `target_path_with_progress`, updated as the scan runs.
+///
+/// Share it with a UI thread through a reference or an `Arc` and load the counters whenever convenient.
+/// Unlike the progress channel there is nothing to drain, and nothing breaks if nobody reads it.
+#[derive(Debug, Default)]
+pub struct ScanProgress {
+    /// Set once after the directory walk.
+    pub files_total: std::sync::atomic::AtomicUsize,
+    pub files_scanned: std::sync::atomic::AtomicUsize,
+    pub combos_found: std::sync::atomic::AtomicUsize,
+}
+
+fn scan_path(
+    config: &Config,
+    path: &Path,
+    sender: Option<&std::sync::mpsc::Sender<ProgressEvent>>,
+    progress: Option<&ScanProgress>,
+) -> Result<(Vec<Combo>, ScanStats), TargetPathError> {
+    let started = std::time::Instant::now();
+    check_target(config, path)?;
     
-    let mut targets = Vec::new();
-    get_targets(&mut targets, &path);
-    if let Some(ref sender) = sender { sender.send(targets.len()).expect("Sending failed"); }
+    let walk = walk(config, path);
+    if walk.targets.is_empty() {
+        log::warn!("No replay files found in {:?}", path);
+        if config.strict { return Err(TargetPathError::NoFilesFound) }
+        if let Some(sender) = sender { let _ = sender.send(ProgressEvent::NoFilesFound); }
+    }
+
+    let (combos, mut stats) = scan_targets(config, &walk.targets, sender, progress);
+    stats.warnings.splice(0..0, walk.warnings);
+    stats.dirs_excluded = walk.dirs_excluded;
+    stats.elapsed = started.elapsed();
+    Ok((combos, stats))
+}
 
-    let mut combo_vec = Vec::new();
+fn scan_targets(
+    config: &Config,
+    targets: &[PathBuf],
+    sender: Option<&std::sync::mpsc::Sender<ProgressEvent>>,
+    progress: Option<&ScanProgress>,
+) -> (Vec<Combo>, ScanStats) {
+    let started = std::time::Instant::now();
+    let mut output = ScanOutput::default();
+    output.stats.files_discovered = targets.len();
+
+    let remaining;
+    let targets = match config.checkpoint {
+        Some(ref checkpoint_path) => {
+            let (processed, resumed) = checkpoint::load(checkpoint_path);
+            remaining = targets.iter().filter(|t| !processed.contains(*t)).cloned().collect::<Vec<_>>();
+
+            // the checkpoint may cover files outside of this scan
+            let in_scan = targets.iter().collect::<std::collections::HashSet<_>>();
+            output.combos = resumed.into_iter().filter(|c| in_scan.contains(&c.path)).collect();
+            output.stats.files_resumed = targets.len() - remaining.len();
+            output.stats.combos_found = output.combos.len();
+            output.checkpoint = Some(checkpoint::Checkpoint::new(checkpoint_path.clone(), config.checkpoint_interval));
+
+            &remaining[..]
+        }
+        None => targets,
+    };
+
+    if let Some(sender) = sender { let _ = sender.send(ProgressEvent::TargetCount(targets.len())); }
+    if let Some(progress) = progress { progress.files_total.store(targets.len(), std::sync::atomic::Ordering::Relaxed); }
 
+    let resumed_combos = output.combos.len();
     {
-        let combo_list = std::sync::Arc::new(std::sync::Mutex::new(&mut combo_vec));
-        if targets.len() < 8 {
+        let combo_list = std::sync::Arc::new(std::sync::Mutex::new(&mut output));
+
+        let thread_count = config.thread_count().min(targets.len());
+
+        #[cfg(target_arch = "wasm32")]
+        let sequential = true;
+        #[cfg(not(target_arch = "wasm32"))]
+        let sequential = thread_count <= 1 || targets.len() < 8;
+
+        // shared between threads so that every thread stops once `max_combos` is reached
+        let found = std::sync::atomic::AtomicUsize::new(resumed_combos);
+        let found = &found;
+        let limit_reached = move || {
+            config.max_combos.is_some_and(|max| found.load(std::sync::atomic::Ordering::Relaxed) >= max)
+        };
+
+        if sequential {
             for t in targets.iter() { 
-                combos(&config, t, &combo_list);
-                if let Some(ref sender) = sender { sender.send(1).expect("Sending failed"); }
+                if limit_reached() { break }
+                let n = combos(&config, t, &combo_list, sender, progress);
+                found.fetch_add(n, std::sync::atomic::Ordering::Relaxed);
             }
         } else {
-            // split into 8 approximately equal slices (why is this so annoying?)
-            let mut slices: [&[PathBuf]; 8] = [&[]; 8];
-            let chunk = targets.len() / 8;
-            let split = (chunk + 1) * (targets.len() % 8);
-            for (i, c) in targets[..split].chunks(chunk+1).chain(targets[split..].chunks(chunk)).enumerate() {
-                slices[i] = c;
-            }
+            // split into approximately equal slices, one per thread
+            let chunk = targets.len() / thread_count;
+            let split = (chunk + 1) * (targets.len() % thread_count);
+            let slices = targets[..split].chunks(chunk+1).chain(targets[split..].chunks(chunk));
             
-            let sender_ref = sender.as_ref();
-
             std::thread::scope(|scope| {
                 for s in slices {
                     let thread_combo_list = combo_list.clone();
                     scope.spawn(move || {
-                        let sender = sender_ref.clone();
-
                         for t in s { 
-                            combos(&config, &t, &thread_combo_list);
-                            if let Some(ref sender) = sender { sender.send(1).expect("Sending failed"); }
+                            if limit_reached() { break }
+                            let n = combos(&config, &t, &thread_combo_list, sender, progress);
+                            found.fetch_add(n, std::sync::atomic::Ordering::Relaxed);
                         }
                     });
                 }
@@ -325,14 +2544,106 @@ pub fn target_path(
     }
     
     // all refs dropped by now
-    Ok(combo_vec)
+
+    // thread scheduling and read_dir order vary between runs
+    output.combos.sort_by(|a, b| a.path.cmp(&b.path).then(a.start.cmp(&b.start)));
+    if let Some(max) = config.max_combos {
+        output.combos.truncate(max);
+        output.stats.combos_found = output.combos.len();
+    }
+
+    if let Some(ref mut checkpoint) = output.checkpoint {
+        checkpoint.flush();
+        if checkpoint.failed {
+            output.stats.warnings.push(ScanWarning::CheckpointWriteFailed(config.checkpoint.clone().unwrap()));
+        }
+    }
+
+    output.stats.elapsed = started.elapsed();
+    (output.combos, output.stats)
+}
+
+/// Walk options and what the walk has seen so far.
+struct Walk<'a> {
+    follow_symlinks: bool,
+    max_depth: Option<usize>,
+    exclude_dirs: &'a [String],
+    extensions: Option<&'a [String]>,
+    root: &'a Path,
+
+    /// Canonical paths of walked directories, so that cycles through symlinks, junctions or bind mounts terminate.
+    visited: std::collections::HashSet<PathBuf>,
+
+    targets: Vec<PathBuf>,
+    warnings: Vec<ScanWarning>,
+    dirs_excluded: usize,
+}
+
+/// Finds every replay under `path`.
+pub(crate) fn walk<'a>(config: &'a Config, path: &'a Path) -> Walk<'a> {
+    let mut walk = Walk {
+        follow_symlinks: config.follow_symlinks,
+        max_depth: config.max_depth,
+        exclude_dirs: &config.exclude_dirs,
+        extensions: config.extensions.as_deref(),
+        root: path,
+        visited: std::collections::HashSet::new(),
+        targets: Vec::new(),
+        warnings: Vec::new(),
+        dirs_excluded: 0,
+    };
+
+    // a single replay, checked by `check_target`
+    if path.is_file() {
+        walk.targets.push(path.to_path_buf());
+        return walk;
+    }
+
+    get_targets(path, 1, &mut walk);
+    walk
+}
+
+/// The path passed to `target_path` must be a folder or a single replay.
+pub(crate) fn check_target(config: &Config, path: &Path) -> Result<(), TargetPathError> {
+    if !matches!(path.try_exists(), Ok(true)) { return Err(TargetPathError::PathNotFound) }
+    if path.is_file() && !has_extension(path, config.extensions.as_deref()) { return Err(TargetPathError::NotAReplay) }
+    Ok(())
+}
+
+/// True if the folder at `path` matches one of `Config::exclude_dirs`.
+fn excluded(walk: &Walk, path: &Path) -> bool {
+    let relative = path.strip_prefix(walk.root).unwrap_or(path);
+    let folders = relative.iter().filter_map(|c| c.to_str()).collect::<Vec<_>>();
+
+    walk.exclude_dirs.iter().any(|pattern| {
+        let pattern = pattern.split(['/', std::path::MAIN_SEPARATOR]).filter(|c| !c.is_empty()).collect::<Vec<_>>();
+        pattern.len() <= folders.len()
+            && pattern.iter().zip(&folders[folders.len() - pattern.len()..]).all(|(p, f)| wildcard_match(p, f))
+    })
 }
 
+/// `depth` is 1 for the folder the walk started in.
 fn get_targets(
-    targets: &mut Vec<std::path::PathBuf>, 
     path: &std::path::Path, 
-) -> Option<()> {
-    for f in std::fs::read_dir(path).ok()? {
+    depth: usize,
+    walk: &mut Walk,
+) {
+    if let Ok(canonical) = std::fs::canonicalize(path) {
+        // already walked through another path
+        if !walk.visited.insert(canonical) { return }
+    }
+
+    let entries = match std::fs::read_dir(path) {
+        Ok(e) => e,
+        Err(_) => {
+            walk.warnings.push(ScanWarning::UnreadableDirectory(path.to_path_buf()));
+            return;
+        }
+    };
+
+    let descend = walk.max_depth.map_or(true, |max| depth < max);
+
+    for f in entries {
         let f = match f {
             Ok(f) => f,
             Err(_) => continue,
@@ -340,40 +2651,350 @@ fn get_targets(
 
         let path = f.path();
 
-        if path.is_dir() { get_targets(targets, &path); }
+        let is_symlink = f.file_type().map_or(false, |t| t.is_symlink());
+        if is_symlink && !path.exists() {
+            walk.warnings.push(ScanWarning::BrokenSymlink(path));
+            continue;
+        }
+
+        if path.is_dir() {
+            if !descend || (is_symlink && !walk.follow_symlinks) { continue; }
+            if excluded(walk, &path) {
+                walk.dirs_excluded += 1;
+                continue;
+            }
+            get_targets(&path, depth + 1, walk);
+            continue;
+        }
         if !path.is_file() { continue; }
-        let ex = path.extension();
-        if ex == Some(std::ffi::OsStr::new("slp")) || ex == Some(std::ffi::OsStr::new("slpz")) {
-            targets.push(path)
+        if has_extension(&path, walk.extensions) { walk.targets.push(path) }
+    }
+}
+
+/// Returns true if `path` contains any of the glob metacharacters `*`, `?` or `[`.
+pub fn is_glob_pattern(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// Expands a glob pattern such as `replays/2024-*/**/*.slp` into the replay files it matches.
+///
+/// `*` and `?` match within a single path component, `[abc]`, `[a-z]` and `[!abc]` match character sets,
+/// and a component of `**` matches any number of directories. Matched directories are walked like
+/// `target_path` does with `config`. As with the directory walk, only files with one of `Config::extensions`
+/// are returned, and a pattern ending in `.slp` also matches the `.slpz` replays beside them.
+pub fn expand_glob(config: &Config, pattern: &str) -> Result<Vec<PathBuf>, TargetPathError> {
+    let is_separator = |c: char| c == '/' || c == std::path::MAIN_SEPARATOR;
+
+    // keep everything before the first component with metacharacters as is, so roots and prefixes survive
+    let glob_start = match pattern.find(['*', '?', '[']) {
+        Some(i) => pattern[..i].rfind(is_separator).map_or(0, |i| i + 1),
+        None => pattern.len(),
+    };
+    let root = PathBuf::from(&pattern[..glob_start]);
+
+    let mut components = pattern[glob_start..]
+        .split(is_separator)
+        .filter(|c| !c.is_empty())
+        .collect::<Vec<_>>();
+    if components.last() == Some(&"**") { components.push("*"); }
+
+    let mut matches = Vec::new();
+    glob_walk(&root, &components, &mut matches);
+
+    let mut targets = Vec::new();
+    for path in matches {
+        if path.is_dir() {
+            targets.extend(walk(config, &path).targets);
+        } else if has_extension(&path, config.extensions.as_deref()) {
+            targets.push(path);
+        }
+    }
+    targets.sort();
+    targets.dedup();
+
+    if targets.is_empty() { return Err(TargetPathError::NoMatches) }
+    Ok(targets)
+}
+
+fn glob_walk(dir: &Path, components: &[&str], matches: &mut Vec<PathBuf>) {
+    let Some((&component, rest)) = components.split_first() else {
+        matches.push(dir.to_path_buf());
+        return;
+    };
+
+    if !is_glob_pattern(component) {
+        let path = dir.join(component);
+        if path.exists() { glob_walk(&path, rest, matches); }
+        return;
+    }
+
+    if component == "**" { glob_walk(dir, rest, matches); }
+
+    let read_dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let entries = match std::fs::read_dir(read_dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = dir.join(entry.file_name());
+
+        if component == "**" {
+            if path.is_dir() { glob_walk(&path, components, matches); }
+            continue;
+        }
+
+        let name = match entry.file_name().into_string() {
+            Ok(n) => n,
+            Err(_) => continue,
+        };
+
+        let matched = wildcard_match(component, &name)
+            || (rest.is_empty() && component.ends_with(".slp") && wildcard_match(&format!("{}z", component), &name));
+
+        if matched && (rest.is_empty() || path.is_dir()) { glob_walk(&path, rest, matches); }
+    }
+}
+
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    wildcard_match_chars(&pattern, &name)
+}
+
+fn wildcard_match_chars(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|i| wildcard_match_chars(&pattern[1..], &name[i..])),
+        Some('?') => !name.is_empty() && wildcard_match_chars(&pattern[1..], &name[1..]),
+        Some('[') => {
+            let Some(close) = pattern.iter().skip(2).position(|&c| c == ']').map(|i| i + 2) else {
+                // unclosed '[' is matched literally
+                return name.first() == Some(&'[') && wildcard_match_chars(&pattern[1..], &name[1..]);
+            };
+            let Some(&c) = name.first() else { return false };
+
+            let (negated, set) = match pattern[1] {
+                '!' => (true, &pattern[2..close]),
+                _ => (false, &pattern[1..close]),
+            };
+
+            let mut in_set = false;
+            let mut i = 0;
+            while i < set.len() {
+                if i + 2 < set.len() && set[i + 1] == '-' {
+                    in_set |= (set[i]..=set[i + 2]).contains(&c);
+                    i += 3;
+                } else {
+                    in_set |= set[i] == c;
+                    i += 1;
+                }
+            }
+
+            in_set != negated && wildcard_match_chars(&pattern[close + 1..], &name[1..])
         }
+        Some(&p) => name.first() == Some(&p) && wildcard_match_chars(&pattern[1..], &name[1..]),
+    }
+}
+
+/// Why a replay that slp_parser failed to read is damaged, if it can be told.
+fn file_damage(path: &Path) -> Option<ScanWarning> {
+    match game_start::check_file(path)? {
+        game_start::FileDamage::Truncated => Some(ScanWarning::TruncatedFile(path.to_path_buf())),
+        game_start::FileDamage::HeaderInvalid => Some(ScanWarning::HeaderInvalid(path.to_path_buf())),
     }
+}
+
+/// slp_parser does not say why a read failed, so a failed read of an `.slpz` file is taken to be a failed decompression.
+fn is_compressed(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e.eq_ignore_ascii_case("slpz"))
+}
+
+/// True if the file name ends in one of `extensions`, or `slp` or `slpz` if `None`, ignoring case.
+fn has_extension(path: &Path, extensions: Option<&[String]>) -> bool {
+    const DEFAULT_EXTENSIONS: &[&str] = &["slp", "slpz"];
+
+    let name = match path.file_name().and_then(|n| n.to_str()) {
+        Some(n) => n.to_lowercase(),
+        None => return false,
+    };
+    let matches = |ext: &str| {
+        let ext = ext.trim_start_matches('.').to_lowercase();
+        name.len() > ext.len() && name.ends_with(&ext) && name[..name.len() - ext.len()].ends_with('.')
+    };
 
-    Some(())
+    match extensions {
+        Some(extensions) => extensions.iter().any(|e| matches(e)),
+        None => DEFAULT_EXTENSIONS.iter().any(|e| matches(e)),
+    }
 }
 
+/// Writes a Slippi playback queue. Combos that end before they start are skipped with a `log::warn!`.
+///
+/// Fails with `ErrorKind::InvalidData` if any combo's path is not valid UTF-8, as Dolphin could not open the
+/// mangled path. The error says how many entries were affected and lists their paths.
+/// Use `write_playlist_with` to write them lossily instead.
 pub fn write_playlist(combos: &[Combo], out_json_path: &std::path::Path) -> std::io::Result<()> {
-    // write json --------------------------------------
-    
+    write_playlist_with(combos, out_json_path, false)
+}
+
+/// Like `write_playlist`, but if `lossy` is true, paths that are not valid UTF-8 are written with their invalid
+/// bytes replaced by U+FFFD rather than failing.
+pub fn write_playlist_with(combos: &[Combo], out_json_path: &std::path::Path, lossy: bool) -> std::io::Result<()> {
+    if !lossy {
+        let non_utf8 = combos.iter().filter(|c| c.path.to_str().is_none()).collect::<Vec<_>>();
+        if !non_utf8.is_empty() {
+            let mut message = format!("{} playlist entries have paths that are not valid UTF-8:", non_utf8.len());
+            for c in non_utf8.iter() {
+                message.push_str(&format!("\n  {}", c.path.display()));
+            }
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, message));
+        }
+    }
+
+    std::fs::write(out_json_path, write_playlist_to_string(combos))
+}
+
+/// Writes only the first `max_entries` combos, e.g. the top 50 after sorting by damage.
+/// Writes every combo if there are fewer than `max_entries`.
+pub fn write_playlist_capped(combos: &[Combo], out_json_path: &std::path::Path, max_entries: usize) -> std::io::Result<()> {
+    write_playlist(&combos[..combos.len().min(max_entries)], out_json_path)
+}
+
+/// Writes a playlist of the `top_n` most damaging combos, e.g. for a highlight reel.
+///
+/// Combos with the same path and frames are written once. They are ranked by `ComboMetadata::damage`, highest first,
+/// and combos without metadata rank last. Ties are broken by path then start frame, so the same combos give the
+/// same playlist in any order.
+pub fn write_highlights(combos: &[Combo], out_json_path: &std::path::Path, top_n: usize) -> std::io::Result<()> {
+    let mut ranked = combos.iter().collect::<Vec<_>>();
+    ranked.sort_by(|a, b| (&a.path, a.start, a.end).cmp(&(&b.path, b.start, b.end)));
+    ranked.dedup_by(|a, b| a.path == b.path && a.start == b.start && a.end == b.end);
+
+    // stable, so ties stay in path order
+    let damage = |c: &Combo| c.metadata.as_ref().map_or(f32::NEG_INFINITY, |m| m.damage());
+    ranked.sort_by(|a, b| damage(b).total_cmp(&damage(a)));
+    ranked.truncate(top_n);
+
+    let ranked = ranked.into_iter().cloned().collect::<Vec<_>>();
+    write_playlist(&ranked, out_json_path)
+}
+
+/// Writes the combos, in order, to numbered playlists `combos_001.json`, `combos_002.json`, ... in `out_dir`,
+/// each with at most `max_per_file` entries. Returns the paths written.
+pub fn write_playlists_chunked(
+    combos: &[Combo],
+    out_dir: &std::path::Path,
+    max_per_file: usize,
+) -> std::io::Result<Vec<PathBuf>> {
+    if max_per_file == 0 {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "max_per_file must be at least 1"));
+    }
+
+    std::fs::create_dir_all(out_dir)?;
+
+    let mut written = Vec::new();
+    for (i, chunk) in combos.chunks(max_per_file).enumerate() {
+        let path = out_dir.join(format!("combos_{:03}.json", i + 1));
+        write_playlist(chunk, &path)?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+/// Paths that are not valid UTF-8 are written lossily, see `write_playlist`.
+pub fn write_playlist_to_string(combos: &[Combo]) -> String {
+    json::stringify_pretty(playlist_json(combos), 2)
+}
+
+/// Reads a playlist from `reader` and writes it back out to `writer`, normalizing its formatting.
+pub fn write_playlist_reader_writer<R: std::io::BufRead, W: std::io::Write>(
+    reader: R,
+    mut writer: W,
+) -> Result<(), ParsePlaylistError> {
+    let combos = parse_playlist_json_reader(reader)?;
+    writer.write_all(write_playlist_to_string(&combos).as_bytes())
+        .map_err(|e| ParsePlaylistError::IoError(e))
+}
+
+/// Combos that are not `Combo::is_valid` are skipped with a warning, since Slippi playback ignores or fails on them.
+fn playlist_json(combos: &[Combo]) -> json::JsonValue {
     let queue_json = combos.iter()
-        .map(|c| json::object!{
+        .enumerate()
+        .filter(|(i, c)| {
+            let valid = c.is_valid(usize::MAX);
+            if !valid { log::warn!("Skipping combo {} in {:?}, it ends before it starts", i, c.path); }
+            valid
+        })
+        .map(|(_, c)| json::object!{
             path: c.path.to_string_lossy().into_owned(),
-            startFrame: c.start as isize - 123,
-            endFrame: c.end as isize - 123,
+            startFrame: c.start.to_slippi_frame(),
+            endFrame: c.end.to_slippi_frame(),
         }).collect::<Vec<_>>();
 
-    let out_json = json::object!{
+    json::object!{
         mode: "queue",
         replay: "",
         queue: queue_json,
-    };
+    }
+}
+
+/// Writes a json array with an entry for each combo, including its metadata if known.
+/// Frames use the same numbering as playlists. Empty names and codes are written as null.
+pub fn write_metadata_json(combos: &[Combo], out_json_path: &std::path::Path) -> std::io::Result<()> {
+    let entries = combos.iter().map(export::combo_json).collect::<Vec<_>>();
+    std::fs::write(out_json_path, json::stringify_pretty(json::JsonValue::Array(entries), 2))
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlaylistStats {
+    pub entries: usize,
+    /// Sum of the length of every entry.
+    pub total_frames: usize,
+    /// Number of entries for each replay, sorted by path.
+    pub per_file: Vec<(PathBuf, usize)>,
+    /// Entries whose replay no longer exists.
+    pub missing_entries: usize,
+}
+
+impl PlaylistStats {
+    /// Total clip time at 60fps.
+    pub fn total_duration(&self) -> std::time::Duration {
+        std::time::Duration::from_secs_f64(self.total_frames as f64 / 60.0)
+    }
+}
+
+/// Summarizes a playlist, e.g. one loaded with `parse_playlist_json`, without reading any replays.
+/// Each replay is checked for existence once.
+pub fn playlist_stats(combos: &[Combo]) -> PlaylistStats {
+    let mut per_file = std::collections::BTreeMap::<&Path, usize>::new();
+    let mut total_frames = 0;
+    for c in combos {
+        *per_file.entry(c.path.as_path()).or_insert(0) += 1;
+        total_frames += c.end.0.saturating_sub(c.start.0);
+    }
 
-    std::fs::write(out_json_path, json::stringify_pretty(out_json, 2))
+    let missing_entries = per_file.iter()
+        .filter(|(path, _)| !matches!(path.try_exists(), Ok(true)))
+        .map(|(_, count)| count)
+        .sum();
+
+    PlaylistStats {
+        entries: combos.len(),
+        total_frames,
+        per_file: per_file.into_iter().map(|(path, count)| (path.to_path_buf(), count)).collect(),
+        missing_entries,
+    }
 }
 
 #[derive(Debug)]
 pub enum ParsePlaylistError {
     JsonParseError(json::Error),
     NotAPlaylistJsonFile,
+    IoError(std::io::Error),
+    /// The queue entry at `entry` has a frame before the first frame of any replay.
+    FrameOutOfRange { entry: usize, frame: i64 },
 }
 
 impl std::fmt::Display for ParsePlaylistError {
@@ -381,25 +3002,248 @@ impl std::fmt::Display for ParsePlaylistError {
         match self {
             ParsePlaylistError::JsonParseError(e) => write!(f, "Invalid json: {}", e),
             ParsePlaylistError::NotAPlaylistJsonFile => write!(f, "File is not a playlist."),
+            ParsePlaylistError::IoError(e) => write!(f, "Could not read playlist: {}", e),
+            ParsePlaylistError::FrameOutOfRange { entry, frame } => {
+                write!(f, "Entry {} has frame {}, which is before the first frame ({}).", entry, frame, FRAME_OFFSET)
+            }
         }
     }
 }
 
+/// Reads until EOF, then parses the playlist.
+pub fn parse_playlist_json_reader<R: std::io::BufRead>(mut reader: R) -> Result<Vec<Combo>, ParsePlaylistError> {
+    let mut file = String::new();
+    reader.read_to_string(&mut file).map_err(|e| ParsePlaylistError::IoError(e))?;
+    parse_playlist_json(&file)
+}
+
 pub fn parse_playlist_json(file: &str) -> Result<Vec<Combo>, ParsePlaylistError> {
     let mut parsed = json::parse(file).map_err(|e| ParsePlaylistError::JsonParseError(e))?;
 
     if parsed["mode"] != "queue" { return Err(ParsePlaylistError::NotAPlaylistJsonFile); }
     if !parsed["queue"].is_array() { return Err(ParsePlaylistError::NotAPlaylistJsonFile); }
 
-    let games = parsed["queue"]
-        .members_mut()
-        .filter_map(|v| {
-            let path = v["path"].take_string()?.into();
-            let start = (v["startFrame"].as_i64()? + 123) as usize;
-            let end = (v["endFrame"].as_i64()? + 123) as usize;
-
-            Some(Combo { path, start, end })
-        }).collect::<Vec<_>>();
+    let mut games = Vec::new();
+    for (entry, v) in parsed["queue"].members_mut().enumerate() {
+        // entries missing a field are skipped
+        let (Some(path), Some(start), Some(end)) = (
+            v["path"].take_string(),
+            v["startFrame"].as_i64(),
+            v["endFrame"].as_i64(),
+        ) else { continue };
+        let frame = |frame| FrameIndex::try_from_slippi_frame(frame).ok_or(ParsePlaylistError::FrameOutOfRange { entry, frame });
+
+        games.push(Combo { path: path.into(), start: frame(start)?, end: frame(end)?, game_ending: false, metadata: None });
+    }
 
     Ok(games)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slp_parser::{ActionState, Character, Frame, StandardActionState::{self, *}};
+
+    fn frame(state: StandardActionState, percent: f32) -> Frame {
+        Frame { state: ActionState::Standard(state), percent, stock_count: 4, ..Frame::default() }
+    }
+
+    /// `combo_start` for a kill on the frame after these, as a scan calls it.
+    fn start(atk: &[Frame], def: &[Frame], config: &Config) -> Option<usize> {
+        combo_start(atk, def, &ComboLookup::new(atk, def, config), config)
+    }
+
+    /// The defender standing for 5 frames, in hitstun for 10 while taking `damage` in total, then standing for 5.
+    fn hit_for(damage: f32) -> (Vec<Frame>, Vec<Frame>) {
+        let atk = vec![frame(Wait, 0.0); 20];
+        let def = (0..20)
+            .map(|f| match f {
+                0..=4 => frame(Wait, 0.0),
+                5..=14 => frame(DamageN1, (f - 4) as f32 * damage / 10.0),
+                _ => frame(Wait, damage),
+            })
+            .collect();
+        (atk, def)
+    }
+
+    #[test]
+    fn passes_rejects_other_characters() {
+        let config = Config { player_character: Some(Character::Fox), ..Config::DEFAULT };
+
+        assert!(passes(&config, Character::Fox, "", "", Character::Marth, "", ""));
+        assert!(!passes(&config, Character::Falco, "", "", Character::Marth, "", ""));
+        // the opponent playing the filtered character does not count
+        assert!(!passes(&config, Character::Marth, "", "", Character::Fox, "", ""));
+    }
+
+    #[test]
+    fn combo_start_rejects_too_little_damage() {
+        // strictness 0 needs 20%, and attacks are not counted here
+        let config = Config { strictness: 0.0, min_attacker_attacks: Some(0), ..Config::DEFAULT };
+
+        let (atk, def) = hit_for(10.0);
+        assert_eq!(start(&atk, &def, &config), None);
+
+        let (atk, def) = hit_for(30.0);
+        assert_eq!(start(&atk, &def, &config), Some(5));
+    }
+
+    #[test]
+    fn combo_start_rejects_frames_past_the_lookup() {
+        let config = Config { strictness: 0.0, min_attacker_attacks: Some(0), ..Config::DEFAULT };
+        let (atk, def) = hit_for(30.0);
+
+        let short_lookup = ComboLookup::new(&atk[..10], &def[..10], &config);
+        assert_eq!(combo_start(&atk, &def, &short_lookup, &config), None);
+        assert_eq!(start(&atk[..19], &def, &config), None);
+    }
+
+    #[test]
+    fn combo_start_handles_tiny_slices() {
+        let config = Config { strictness: 0.0, min_attacker_attacks: Some(0), ..Config::DEFAULT };
+
+        assert_eq!(start(&[], &[], &config), None);
+        assert_eq!(find_combo_start(&[], &[], &config), None);
+
+        // the last hit ends on the only frame
+        let one = [frame(DamageN1, 50.0)];
+        assert_eq!(start(&[frame(Wait, 0.0)], &one, &config), None);
+
+        // in hitstun from the first frame, so there is no frame before the first hit to measure damage from
+        let atk = vec![frame(Wait, 0.0); 5];
+        let def = [
+            frame(DamageN1, 0.0), frame(DamageN1, 30.0), frame(DamageN1, 60.0), frame(DamageN1, 90.0),
+            frame(Wait, 120.0),
+        ];
+        assert_eq!(start(&atk, &def, &config), Some(0));
+        assert_eq!(find_combo_start(&atk, &def, &config), Some(0));
+    }
+
+    fn stocks(state: StandardActionState, stock_count: u8) -> Frame {
+        Frame { stock_count: stock_count as _, ..frame(state, 0.0) }
+    }
+
+    #[test]
+    fn took_stock_needs_a_stock_lost() {
+        // dead briefly without losing a stock
+        let frames = [stocks(Wait, 4), stocks(DeadDown, 4), stocks(DeadDown, 4), stocks(Wait, 4)];
+        assert!(!took_stock(&frames, 1, 3));
+        assert_eq!(stock_losses(&frames), Vec::new());
+
+        // the stock count drops partway through the dead frames
+        let frames = [stocks(Wait, 4), stocks(DeadDown, 4), stocks(DeadDown, 3), stocks(Wait, 3)];
+        assert!(took_stock(&frames, 1, 3));
+        assert_eq!(stock_losses(&frames), vec![(1, 3)]);
+
+        // the replay ends before the stock count is updated
+        let frames = [stocks(Wait, 1), stocks(DeadDown, 1), stocks(DeadDown, 1)];
+        assert!(took_stock(&frames, 1, 3));
+        assert_eq!(stock_losses(&frames), vec![(1, 3)]);
+    }
+
+    #[test]
+    fn classify_opener_ignores_frames_past_the_end() {
+        let atk = [frame(Wait, 0.0), frame(AttackAirB, 0.0), frame(AttackAirB, 0.0)];
+
+        assert_eq!(classify_opener(&atk, 2), Some(ComboOpener::BackAir));
+        assert_eq!(classify_opener(&atk, 3), None);
+        assert_eq!(classify_opener(&[], 0), None);
+    }
+
+    #[test]
+    fn classify_ender_rejects_frames_past_the_end() {
+        let atk = [frame(Wait, 0.0), frame(AttackHi4, 0.0)];
+        let def = [frame(DamageN1, 0.0), frame(DamageFlyHi, 150.0)];
+
+        assert_eq!(classify_ender(&atk, &def, 1), Some(ComboEnder::UpSmash));
+        assert_eq!(classify_ender(&atk, &def, 2), None);
+        assert_eq!(classify_ender(&atk, &def[..1], 0), None);
+    }
+
+    #[test]
+    fn invalid_names_decode_lossily_with_a_warning() {
+        let path = Path::new("game.slp");
+        let mut warnings = Vec::new();
+
+        let (text, [name_end]) = decode_names(path, [&b"AB\xFFC\0\0\0"[..]], &mut warnings);
+        assert_eq!(&text[..name_end], "AB\u{FFFD}C");
+        assert_eq!(warnings, vec![ScanWarning::InvalidNames(path.to_path_buf())]);
+
+        let mut warnings = Vec::new();
+        let (text, [name_end, code_end]) = decode_names(path, [&b"Fox\0"[..], &b"\xFF\xFF\0"[..]], &mut warnings);
+        assert_eq!(&text[name_end..code_end], "\u{FFFD}\u{FFFD}");
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn stock_filters_check_attacker_and_defender() {
+        // a comeback: the attacker on their last stock at 120%+ taking the defender's last stock
+        let config = Config {
+            max_defender_stocks: Some(1),
+            min_attacker_percent: Some(120.0),
+            attacker_last_stock: true,
+            ..Config::DEFAULT
+        };
+        let atk = |percent, stock_count: u8| vec![Frame { percent, ..stocks(Wait, stock_count) }; 10];
+        let def = |stock_count| vec![stocks(Wait, stock_count); 10];
+
+        assert!(passes_stock_filters(&config, &atk(130.0, 1), &def(1), 2, 8));
+        // each constraint alone rejects the combo
+        assert!(!passes_stock_filters(&config, &atk(130.0, 1), &def(2), 2, 8));
+        assert!(!passes_stock_filters(&config, &atk(100.0, 1), &def(1), 2, 8));
+        assert!(!passes_stock_filters(&config, &atk(130.0, 2), &def(1), 2, 8));
+    }
+
+    #[test]
+    fn expand_glob_uses_config_extensions() {
+        let dir = std::env::temp_dir().join(format!("slp_combo_finder_glob_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        for name in ["a.slp", "b.slp.bak", "sub/c.slp.bak"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let pattern = format!("{}/*", dir.display());
+        let config = Config { extensions: Some(vec!["slp.bak".to_string()]), ..Config::DEFAULT };
+        let found = expand_glob(&config, &pattern);
+        let default = expand_glob(&Config::DEFAULT, &pattern);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found.unwrap(), vec![dir.join("b.slp.bak"), dir.join("sub/c.slp.bak")]);
+        assert_eq!(default.unwrap(), vec![dir.join("a.slp")]);
+    }
+
+    #[test]
+    fn effective_lead_in_uses_the_override() {
+        let config = Config {
+            lead_in_override: Some(HashMap::from([(Character::Jigglypuff, 90)])),
+            ..Config::DEFAULT
+        };
+
+        assert_eq!(config.effective_lead_in(Character::Jigglypuff), 90);
+        assert_eq!(config.effective_lead_in(Character::Fox), Config::DEFAULT.lead_in);
+        assert_eq!(config.effective_lead_out(Character::Jigglypuff), Config::DEFAULT.lead_out);
+    }
+
+    #[test]
+    fn game_and_watch_spellings_parse() {
+        for name in ["Game & Watch", "game & watch", "game and watch", "Mr. Game & Watch", "game-n-watch", "GnW", "gw"] {
+            assert_eq!(parse_character(name), Some(Character::GameAndWatch), "{}", name);
+        }
+        assert_eq!(parse_character("game"), None);
+    }
+
+    #[test]
+    fn connected_hits_counts_each_new_hit() {
+        let def = [
+            frame(Wait, 0.0),
+            frame(DamageN1, 10.0), frame(DamageN1, 10.0), frame(DamageN1, 10.0),
+            frame(Wait, 10.0),
+            frame(DamageN1, 20.0), frame(DamageN1, 20.0),
+            // a second hit before hitstun ends
+            frame(DamageN1, 30.0),
+        ];
+
+        assert_eq!(connected_hits(&def, 1, 7), 3);
+        assert_eq!(connected_hits(&def, 1, 4), 1);
+    }
+}
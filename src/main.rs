@@ -1,25 +1,401 @@
 use std::path::{PathBuf, Path};
 
-const USAGE: &'static str = "Usage: combo_finder <slp or folder path> <strictness> [out path]";
+#[cfg(feature = "server")]
+mod server;
+use slp_combo_finder::{Combo, Config, ProgressEvent, ScanStats};
+
+const USAGE: &'static str = "\
+Usage: combo_finder <command> [args]
+
+Commands:
+  find       Find combos in replays and write them to a playlist
+  merge      Combine several playlists into one
+  validate   Check a playlist for missing replays and invalid frame ranges
+  stats      Summarize a playlist without reading its replays
+  serve      Serve combo searches over http (requires the 'server' feature)
+  help       Print help for a command
+
+'combo_finder <slp or folder path> <strictness> [out path]' is still accepted as 'find'.";
+
+const FIND_USAGE: &'static str = "Usage: combo_finder find [options] <slp or folder path> <strictness> [out path]";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Format {
+    Queue,
+    Csv,
+    Jsonl,
+    Srt,
+    Timestamps,
+}
+
+impl Format {
+    fn parse(s: &str) -> Option<Format> {
+        match s {
+            "queue" => Some(Format::Queue),
+            "csv" => Some(Format::Csv),
+            "jsonl" => Some(Format::Jsonl),
+            "srt" => Some(Format::Srt),
+            "timestamps" => Some(Format::Timestamps),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Queue => "json",
+            Format::Csv => "csv",
+            Format::Jsonl => "jsonl",
+            Format::Srt => "srt",
+            Format::Timestamps => "txt",
+        }
+    }
+}
+
+// exit codes, 1 is used for invalid arguments and other input errors
+const EXIT_TOO_FEW_COMBOS: i32 = 2;
+const EXIT_PARSE_FAILURES: i32 = 3;
+const MERGE_USAGE: &'static str = "\
+Usage: combo_finder merge [-o <out path>] <playlists...>
+
+Writes the entries of every playlist, in order, to one playlist [default out path: combos.json].";
+const SERVE_USAGE: &'static str = "\
+Usage: combo_finder serve [--addr <address>]
+
+Serves 'GET /health' and 'POST /search' [default address: 127.0.0.1:8080].
+The search body is a json object of config fields plus the 'path' to scan, e.g.
+{\"path\": \"replays\", \"strictness\": 0.6, \"player_code\": \"ABCD#123\"}
+and the response is the playlist json.";
+const STATS_USAGE: &'static str = "\
+Usage: combo_finder stats [-v] <playlist>
+
+Prints the number of entries, total clip time and entries whose replay no longer exists.
+With -v, also prints the number of entries per replay.";
+const VALIDATE_USAGE: &'static str = "\
+Usage: combo_finder validate <playlist>
+
+Exits with an error if the playlist cannot be parsed, references missing replays, or has entries
+that end before they start.";
+
+/// Lead in/out values above this many frames get a warning, as they are probably a mistake.
+const LEAD_WARNING_FRAMES: usize = 60 * 60;
+
+fn find_options() -> String {
+    format!("\
+Options:
+  -v, --verbose            Print each combo as it is found
+  -q, --quiet              Don't print the summary after the scan
+      --json-summary       Print the summary as a json object on stdout instead of text on stderr
+  -f, --format <FORMAT>    Output format, may be given several times to write several outputs
+                           [possible values: queue, csv, jsonl, srt, timestamps] [default: queue]
+      --fps <N>            Frames per second clips play at, required by srt
+      --gap <SECONDS>      Seconds between clips, required by srt, optional for timestamps
+      --count-only         Print the number of combos found instead of writing a playlist
+      --lossy-paths        Write paths that are not valid UTF-8 to the queue lossily instead of failing
+      --min-combos <N>     Fail if fewer than N combos are found [default: 1]
+      --max-parse-failures <N>
+                           Fail if more than N files cannot be parsed
+      --follow-symlinks    Walk into symlinked folders, each folder at most once
+      --strict             Fail if no replay files are found, rather than finding no combos
+      --max-depth <N>      How many folders deep to look for replays, 1 for only the given folder
+      --ext <EXTENSION>    Treat files with this extension as replays, may be given several times,
+                           e.g. --ext slp --ext slp.bak [default: slp and slpz]
+      --exclude <PATTERN>  Skip folders with this name, may be given several times. Supports * ? [...]
+                           wildcards, and patterns with a '/' match the end of the folder's path
+      --checkpoint <FILE>  Record progress in FILE, and skip the files it lists, to resume an interrupted scan
+      --checkpoint-interval <N>
+                           Files scanned between checkpoint writes [default: {}]
+  -j, --jobs <N>           Number of threads to scan with, 1 for deterministic single-threaded scans [default: {}]
+      --lead-in <N>        Frames of context before each combo, or seconds with an 's' suffix [default: {}]
+      --lead-out <N>       Frames of context after each combo, or seconds with an 's' suffix [default: {}]
+      --me <CODE>          Only combos performed by this connect code
+      --vs <CODE>          Only combos performed against this connect code
+      --in-game <CODE>     Only games this connect code played in, by either player
+      --char <CHARACTER>   Only combos performed by this character, or any of a comma separated list
+      --vs-char <CHARACTER>
+                           Only combos performed against this character, or any of a comma separated list
+      --name <NAME>        Only combos performed by this display name
+      --vs-name <NAME>     Only combos performed against this display name
+      --match-type <TYPE>  Only online games of this type [possible values: ranked, unranked, direct]
+      --max-combos <N>     Stop scanning once N combos are found
+      --flawless           Only combos in which the attacker was never hit, not even in shield
+      --flawless-shield    Like --flawless, but allow hits blocked by the attacker's shield
+      --max-attacker-damage <P>
+                           Only combos in which the attacker took at most P% between the first hit and the kill
+      --true-combos        Only combos the defender could not act out of between hits
+      --tumble-ok          With --true-combos, treat tumble as inescapable
+      --tech               Tag combos opened with a waveshine or wavedash
+      --out-of-shield <N>  Only punishes out of shield, starting within N frames of shielding
+      --opener <MOVE>      Only combos opened by this kind of move
+                           [possible values: grab, downsmash, forwardsmash, backair, neutralair]
+      --ender <KILL>       Only combos ended by this kind of kill
+                           [possible values: backair, forwardsmash, upsmash, spike, edgeguard]
+      --min-moves <N>      Only combos using at least N different attacker moves
+      --min-hits <N>       Only combos in which at least N hits connected, counting each hit of multi-hit moves
+      --min-attacks <N>    Only combos in which the attacker started at least N attacks, overriding strictness
+      --min-version <VERSION>
+                           Skip replays recorded with a Slippi version older than this, e.g. 3.14.0
+      --won                Only games won by the player matched by --me, --name or --char.
+                           Games ended by a timeout, LRAS or disconnect are skipped
+      --max-hit-gap <N>    Only combos with at most N frames between hits, or seconds with an 's' suffix
+      --min-game-damage <P>
+                           Skip games in which the opponent took less than P% in total
+      --min-game-length <N>
+                           Skip games shorter than N frames, or seconds with an 's' suffix
+      --max-game-length <N>
+                           Skip games longer than N frames, or seconds with an 's' suffix
+      --max-grab-ratio <R> Only combos where at most this fraction of hits were throws, from 0 to 1
+      --last-stock         Only combos that ended with both players on their last stock
+      --min-attacker-percent <P>
+                           Only combos started while the attacker was at P% or higher
+      --attacker-last-stock
+                           Only combos started while the attacker was on their last stock
+  -h, --help               Print this help
+
+Flags may appear anywhere, either as '--flag value' or '--flag=value'.
+Most options may also be set with SLP_-prefixed environment variables named after the library's
+Config fields, e.g. SLP_STRICTNESS, SLP_PLAYER_CODE or SLP_THREADS, which flags override.
+The strictness argument may be left out if SLP_STRICTNESS is set.
+The input path may be a glob pattern such as 'replays/2024-*/**/*.slp'. Quote it so the shell
+does not expand it. A pattern ending in '.slp' also matches '.slpz' replays.
+The out path defaults to 'combos' with the format's extension. When several formats are given,
+each output uses the out path with its format's extension.
+
+Exit codes:
+  0  Combos were found and written
+  1  Invalid arguments or input path
+  2  Fewer combos than --min-combos were found, nothing was written
+  3  More files than --max-parse-failures could not be parsed, nothing was written",
+        Config::DEFAULT.checkpoint_interval,
+        Config::DEFAULT.thread_count(),
+        Config::DEFAULT.lead_in,
+        Config::DEFAULT.lead_out,
+    )
+}
 
 fn main() {
-    let mut args = std::env::args();
-    args.next();
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    match args.first().map(String::as_str) {
+        Some("find") => find(&args[1..]),
+        Some("merge") => merge(&args[1..]),
+        Some("validate") => validate(&args[1..]),
+        Some("serve") => serve(&args[1..]),
+        Some("stats") => stats(&args[1..]),
+        Some("help") => help(args.get(1).map(String::as_str)),
+        Some("-h" | "--help") => help(None),
+        None => {
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+        // legacy invocation without a command
+        Some(_) => find(&args),
+    }
+}
+
+fn help(command: Option<&str>) {
+    match command {
+        None => println!("{}", USAGE),
+        Some("find") => println!("{}\n\n{}", FIND_USAGE, find_options()),
+        Some("merge") => println!("{}", MERGE_USAGE),
+        Some("validate") => println!("{}", VALIDATE_USAGE),
+        Some("serve") => println!("{}", SERVE_USAGE),
+        Some("stats") => println!("{}", STATS_USAGE),
+        Some(c) => {
+            eprintln!("Error: unknown command '{}'", c);
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Splits '--flag=value' into its flag and value. Other arguments are returned as is.
+fn split_flag(arg: &str) -> (String, Option<String>) {
+    match arg.split_once('=') {
+        Some((f, v)) if f.starts_with("--") => (f.to_string(), Some(v.to_string())),
+        _ => (arg.to_string(), None),
+    }
+}
+
+fn unknown_option(flag: &str, usage: &str) -> ! {
+    eprintln!("Error: unknown option '{}'", flag);
+    eprintln!("{}", usage);
+    std::process::exit(1);
+}
+
+fn find(args: &[String]) {
+    // flags override the environment
+    let mut config = match Config::from_env() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let mut verbose = false;
+    let mut count_only = false;
+    let mut lossy_paths = false;
+    let mut quiet = false;
+    let mut json_summary = false;
+    let mut min_combos = 1;
+    let mut max_parse_failures = None;
+    let mut formats = Vec::new();
+    let mut fps = None;
+    let mut gap = None;
+    let mut positional = Vec::new();
+
+    let mut args = args.iter().cloned();
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = split_flag(&arg);
+
+        match flag.as_str() {
+            "-h" | "--help" => {
+                help(Some("find"));
+                std::process::exit(0);
+            }
+            "-v" | "--verbose" => verbose = true,
+            "--count-only" => count_only = true,
+            "--lossy-paths" => lossy_paths = true,
+            "--match-type" => {
+                let value = flag_value(&flag, inline_value, &mut args);
+                match slp_combo_finder::MatchType::parse(&value) {
+                    Some(m) => config.match_type = Some(m),
+                    None => {
+                        eprintln!("Error: unknown match type '{}', expected one of: ranked, unranked, direct", value);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--max-combos" => config.max_combos = Some(count(&flag, &flag_value(&flag, inline_value, &mut args))),
+            "--flawless" => config.flawless = true,
+            "--max-attacker-damage" => config.max_attacker_damage = Some(non_negative(&flag, &flag_value(&flag, inline_value, &mut args))),
+            "--flawless-shield" => {
+                config.flawless = true;
+                config.flawless_allow_shield_hits = true;
+            }
+            "--true-combos" => config.true_combos_only = true,
+            "--tumble-ok" => config.tumble_breaks_true_combo = false,
+            "--tech" => config.detect_tech = true,
+            "--out-of-shield" => config.out_of_shield_frames = Some(frames(&flag, &flag_value(&flag, inline_value, &mut args))),
+            "--min-attacks" => config.min_attacker_attacks = Some(count(&flag, &flag_value(&flag, inline_value, &mut args))),
+            "--opener" => {
+                let value = flag_value(&flag, inline_value, &mut args);
+                match slp_combo_finder::ComboOpener::parse(&value) {
+                    Some(o) => config.opener = Some(o),
+                    None => {
+                        eprintln!("Error: unknown opener '{}', expected one of: grab, downsmash, forwardsmash, backair, neutralair", value);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--min-hits" => config.min_connected_hits = Some(count(&flag, &flag_value(&flag, inline_value, &mut args))),
+            "--ender" => {
+                let value = flag_value(&flag, inline_value, &mut args);
+                match slp_combo_finder::ComboEnder::parse(&value) {
+                    Some(e) => config.ender = Some(e),
+                    None => {
+                        eprintln!("Error: unknown ender '{}', expected one of: backair, forwardsmash, upsmash, spike, edgeguard", value);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--min-moves" => config.min_distinct_moves = Some(count(&flag, &flag_value(&flag, inline_value, &mut args))),
+            "--min-version" => {
+                let value = flag_value(&flag, inline_value, &mut args);
+                match slp_combo_finder::parse_version(&value) {
+                    Some(v) => config.min_version = Some(v),
+                    None => {
+                        eprintln!("Error: invalid version '{}', expected e.g. 3.14.0", value);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--won" => config.player_won_only = true,
+            "--max-hit-gap" => config.max_hit_gap_frames = Some(frames(&flag, &flag_value(&flag, inline_value, &mut args))),
+            "--min-game-length" => config.min_game_duration_frames = Some(frames(&flag, &flag_value(&flag, inline_value, &mut args))),
+            "--max-game-length" => config.max_game_duration_frames = Some(frames(&flag, &flag_value(&flag, inline_value, &mut args))),
+            "--min-game-damage" => config.min_total_game_damage = Some(non_negative(&flag, &flag_value(&flag, inline_value, &mut args))),
+            "--max-grab-ratio" => {
+                let value = flag_value(&flag, inline_value, &mut args);
+                match value.parse::<f32>() {
+                    Ok(r) if (0.0..=1.0).contains(&r) => config.max_grab_ratio = Some(r),
+                    _ => {
+                        eprintln!("Error: invalid value '{}' for '{}', expected a number between 0 and 1", value, flag);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--min-attacker-percent" => config.min_attacker_percent = Some(non_negative(&flag, &flag_value(&flag, inline_value, &mut args))),
+            "--attacker-last-stock" => config.attacker_last_stock = true,
+            "--last-stock" => {
+                config.max_defender_stocks = Some(1);
+                config.max_attacker_stocks = Some(1);
+            }
+            "--follow-symlinks" => config.follow_symlinks = true,
+            "--strict" => config.strict = true,
+            "--checkpoint" => config.checkpoint = Some(flag_value(&flag, inline_value, &mut args).into()),
+            "--checkpoint-interval" => config.checkpoint_interval = positive(&flag, &flag_value(&flag, inline_value, &mut args)),
+            "--ext" => config.extensions.get_or_insert_with(Vec::new).push(flag_value(&flag, inline_value, &mut args)),
+            "--exclude" => config.exclude_dirs.push(flag_value(&flag, inline_value, &mut args)),
+            "--max-depth" => config.max_depth = Some(positive(&flag, &flag_value(&flag, inline_value, &mut args))),
+            "-q" | "--quiet" => quiet = true,
+            "--json-summary" => json_summary = true,
+            "-f" | "--format" => {
+                let value = flag_value(&flag, inline_value, &mut args);
+                match Format::parse(&value) {
+                    Some(f) if !formats.contains(&f) => formats.push(f),
+                    Some(_) => (),
+                    None => {
+                        eprintln!("Error: unknown format '{}', expected one of: queue, csv, jsonl, srt, timestamps", value);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--fps" => fps = Some(non_negative(&flag, &flag_value(&flag, inline_value, &mut args))),
+            "--gap" => gap = Some(non_negative(&flag, &flag_value(&flag, inline_value, &mut args))),
+            "--min-combos" => min_combos = count(&flag, &flag_value(&flag, inline_value, &mut args)),
+            "--max-parse-failures" => max_parse_failures = Some(count(&flag, &flag_value(&flag, inline_value, &mut args))),
+            "-j" | "--jobs" => config.threads = Some(positive(&flag, &flag_value(&flag, inline_value, &mut args))),
+            "--lead-in" => config.lead_in = frames(&flag, &flag_value(&flag, inline_value, &mut args)),
+            "--lead-out" => config.lead_out = frames(&flag, &flag_value(&flag, inline_value, &mut args)),
+            "--me" => config.player_code = Some(flag_value(&flag, inline_value, &mut args)),
+            "--vs" => config.opponent_code = Some(flag_value(&flag, inline_value, &mut args)),
+            "--name" => config.player_name = Some(flag_value(&flag, inline_value, &mut args)),
+            "--vs-name" => config.opponent_name = Some(flag_value(&flag, inline_value, &mut args)),
+            "--char" => config.player_characters = Some(characters(&flag_value(&flag, inline_value, &mut args))),
+            "--in-game" => config.either_player_code = Some(flag_value(&flag, inline_value, &mut args)),
+            "--vs-char" => config.opponent_characters = Some(characters(&flag_value(&flag, inline_value, &mut args))),
+            f if f.starts_with('-') && f.len() > 1 => unknown_option(f, FIND_USAGE),
+            _ => positional.push(arg),
+        }
+    }
+    let mut args = positional.into_iter();
 
     let input_path: PathBuf = match args.next() {
         Some(f) => f.into(),
         None => {
-            eprintln!("{}", USAGE);
+            eprintln!("{}", FIND_USAGE);
             std::process::exit(1);
         }
     };
 
-    if !input_path.exists() {
+    // quote patterns to stop the shell expanding them first
+    let glob_targets = match input_path.to_str() {
+        Some(pattern) if slp_combo_finder::is_glob_pattern(pattern) => match slp_combo_finder::expand_glob(&config, pattern) {
+            Ok(targets) => Some(targets),
+            Err(_) => {
+                eprintln!("Error: '{}' did not match any replays", pattern);
+                std::process::exit(1);
+            }
+        },
+        _ => None,
+    };
+
+    if glob_targets.is_none() && !input_path.exists() {
         eprintln!("Error: input path does not exist");
         std::process::exit(1);
     }
 
-    let strictness = match args.next() {
+    config.strictness = match args.next() {
         Some(n) => match n.parse::<f32>() {
             Ok(n) if (0.0..=1.0).contains(&n) => n,
             _ => {
@@ -27,30 +403,438 @@ fn main() {
                 std::process::exit(1);
             }
         }
+        // empty means unset, as in `Config::from_env`
+        None if std::env::var("SLP_STRICTNESS").is_ok_and(|v| !v.is_empty()) => config.strictness,
         None => {
-            eprintln!("{}", USAGE);
+            eprintln!("{}", FIND_USAGE);
+            std::process::exit(1);
+        }
+    };
+
+    if formats.is_empty() { formats.push(Format::Queue); }
+
+    if formats.contains(&Format::Srt) && (fps.is_none() || gap.is_none()) {
+        eprintln!("Error: the srt format requires --fps and --gap");
+        std::process::exit(1);
+    }
+    if fps == Some(0.0) {
+        eprintln!("Error: --fps must be greater than 0");
+        std::process::exit(1);
+    }
+
+    let out_path = args.next().map(PathBuf::from);
+    let out_paths = formats.iter()
+        .map(|&f| match out_path {
+            Some(ref p) if formats.len() == 1 => (f, p.clone()),
+            Some(ref p) => (f, p.with_extension(f.extension())),
+            None => (f, PathBuf::from("combos").with_extension(f.extension())),
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(extra) = args.next() {
+        eprintln!("Error: unexpected argument '{}'", extra);
+        eprintln!("{}", FIND_USAGE);
+        std::process::exit(1);
+    }
+
+    let input_path = Path::new(&input_path);
+    let scan = |config: &Config, sender| match glob_targets {
+        Some(ref targets) => slp_combo_finder::target_paths_with_stats(config, targets, sender),
+        None => slp_combo_finder::target_path_with_stats(config, input_path, sender),
+    };
+    let scan_start = std::time::Instant::now();
+    let scanned = if verbose {
+        // combos are printed to stdout as they are found, progress goes to stderr
+        let (sender, receiver) = std::sync::mpsc::channel();
+        eprintln!("Using {} threads", config.thread_count());
+        eprintln!("{}", slp_combo_finder::ComboHeuristics::debug_values(config.strictness));
+        let config = &config;
+        std::thread::scope(|scope| {
+            let scan = scope.spawn(move || scan(config, Some(sender)));
+
+            for event in receiver {
+                match event {
+                    ProgressEvent::NoFilesFound => eprintln!("No replay files found"),
+                    ProgressEvent::TargetCount(n) => eprintln!("Scanning {} files", n),
+                    ProgressEvent::FileScanned => (),
+                    ProgressEvent::ComboFound(combo) if !count_only => print_combo(&combo),
+                    ProgressEvent::ComboFound(_) => (),
+                    ProgressEvent::ParseFailed(path) => eprintln!("Failed to parse {}", path.display()),
+                }
+            }
+
+            scan.join().unwrap()
+        })
+    } else {
+        scan(&config, None)
+    };
+    let (combos, stats) = match scanned {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let elapsed = scan_start.elapsed();
+
+    if !quiet {
+        for warning in stats.warnings.iter() { eprintln!("Warning: {}", warning); }
+    }
+    if json_summary {
+        println!("{}", summary_json(&combos, &stats, elapsed).dump());
+    } else if !quiet {
+        eprintln!("{}", summary(&combos, &stats, elapsed));
+    }
+
+    if count_only {
+        if verbose {
+            let mut per_file = std::collections::BTreeMap::<&Path, usize>::new();
+            for combo in combos.iter() { *per_file.entry(combo.path.as_path()).or_insert(0) += 1; }
+            for (path, count) in per_file { println!("{:>5}  {}", count, path.display()); }
+
+            let mut damage = combos.iter()
+                .filter_map(|c| c.metadata.as_ref().map(|m| m.damage()))
+                .collect::<Vec<_>>();
+            if !damage.is_empty() {
+                damage.sort_by(|a, b| a.total_cmp(b));
+                println!(
+                    "damage: min {:.0}%, median {:.0}%, max {:.0}%",
+                    damage[0], damage[damage.len() / 2], damage[damage.len() - 1],
+                );
+            }
+        }
+        println!("{}", combos.len());
+    }
+
+    if let Some(max) = max_parse_failures {
+        if stats.parse_failures > max {
+            eprintln!("Error: {} of {} files could not be parsed", stats.parse_failures, stats.files_discovered);
+            std::process::exit(EXIT_PARSE_FAILURES);
+        }
+    }
+
+    if combos.len() < min_combos {
+        eprintln!("{}", too_few_combos_hint(&config, &stats, min_combos));
+        std::process::exit(EXIT_TOO_FEW_COMBOS);
+    }
+
+    if !count_only {
+        for (format, path) in out_paths.iter() {
+            let written = match format {
+                Format::Queue => slp_combo_finder::write_playlist_with(&combos, path, lossy_paths),
+                Format::Csv => slp_combo_finder::write_csv(&combos, path),
+                Format::Jsonl => slp_combo_finder::write_jsonl(&combos, path),
+                Format::Srt => slp_combo_finder::write_srt(&combos, path, fps.unwrap(), gap.unwrap()),
+                Format::Timestamps => slp_combo_finder::write_timestamps(&combos, path, gap.unwrap_or(0.0)),
+            };
+
+            if let Err(e) = written {
+                eprintln!("Error: could not write '{}': {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+fn clip_frames(combos: &[Combo]) -> usize {
+    combos.iter().map(|c| c.end.0.saturating_sub(c.start.0)).sum()
+}
+
+fn summary(combos: &[Combo], stats: &ScanStats, elapsed: std::time::Duration) -> String {
+    let clip_seconds = clip_frames(combos) / 60;
+    let mut notes = Vec::new();
+    if stats.dirs_excluded > 0 { notes.push(format!("{} folders excluded", stats.dirs_excluded)); }
+    if stats.files_resumed > 0 { notes.push(format!("{} resumed from checkpoint", stats.files_resumed)); }
+    let notes = if notes.is_empty() { String::new() } else { format!(" ({})", notes.join(", ")) };
+    format!(
+        "Scanned {} files{}: {} analyzed, {} skipped by filters, {} too old, {} failed to parse.\n\
+        Found {} combos ({}:{:02} of clips) in {:.1}s.",
+        stats.files_discovered, notes, stats.files_analyzed, stats.files_skipped, stats.files_too_old, stats.parse_failures,
+        stats.combos_found, clip_seconds / 60, clip_seconds % 60, elapsed.as_secs_f32(),
+    )
+}
+
+fn summary_json(combos: &[Combo], stats: &ScanStats, elapsed: std::time::Duration) -> json::JsonValue {
+    json::object!{
+        filesDiscovered: stats.files_discovered,
+        filesAnalyzed: stats.files_analyzed,
+        filesSkipped: stats.files_skipped,
+        filesTooOld: stats.files_too_old,
+        parseFailures: stats.parse_failures,
+        combosFound: stats.combos_found,
+        dirsExcluded: stats.dirs_excluded,
+        filesResumed: stats.files_resumed,
+        clipSeconds: clip_frames(combos) as f64 / 60.0,
+        warnings: stats.warnings.iter().map(|w| w.to_string()).collect::<Vec<_>>(),
+        elapsedSeconds: elapsed.as_secs_f64(),
+    }
+}
+
+fn too_few_combos_hint(config: &Config, stats: &ScanStats, min_combos: usize) -> String {
+    let mut hint = format!("Found {} combos in {} scanned files", stats.combos_found, stats.files_analyzed);
+    if min_combos > 1 { hint.push_str(&format!(", at least {} required", min_combos)); }
+    hint.push_str(". No playlist written.");
+
+    if stats.files_discovered == 0 {
+        hint.push_str("\nHint: no .slp or .slpz files were found in the input path.");
+    } else if stats.files_analyzed == 0 && stats.files_skipped != 0 {
+        hint.push_str(&format!("\nHint: all {} files were skipped by the player filters.", stats.files_skipped));
+    } else if config.strictness > 0.5 {
+        hint.push_str(&format!("\nHint: strictness {} may be too strict.", config.strictness));
+    }
+
+    hint
+}
+
+fn merge(args: &[String]) {
+    let mut out_path = "combos.json".to_string();
+    let mut playlists = Vec::new();
+
+    let mut args = args.iter().cloned();
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = split_flag(&arg);
+
+        match flag.as_str() {
+            "-h" | "--help" => {
+                help(Some("merge"));
+                std::process::exit(0);
+            }
+            "-o" | "--out" => out_path = flag_value(&flag, inline_value, &mut args),
+            f if f.starts_with('-') && f.len() > 1 => unknown_option(f, MERGE_USAGE),
+            _ => playlists.push(arg),
+        }
+    }
+
+    if playlists.is_empty() {
+        eprintln!("{}", MERGE_USAGE);
+        std::process::exit(1);
+    }
+
+    let mut combos = Vec::new();
+    for playlist in playlists.iter() {
+        combos.extend(read_playlist(Path::new(playlist)));
+    }
+
+    if let Err(e) = slp_combo_finder::write_playlist(&combos, Path::new(&out_path)) {
+        eprintln!("Error: could not write '{}': {}", out_path, e);
+        std::process::exit(1);
+    }
+}
+
+fn validate(args: &[String]) {
+    let playlist = match args {
+        [flag] if flag == "-h" || flag == "--help" => {
+            help(Some("validate"));
+            std::process::exit(0);
+        }
+        [playlist] => Path::new(playlist),
+        _ => {
+            eprintln!("{}", VALIDATE_USAGE);
             std::process::exit(1);
         }
     };
 
-    let out_json_path = match args.next() {
-        Some(p) => p,
-        None => "combos.json".to_string(),
+    let combos = read_playlist(playlist);
+
+    let mut problems = 0;
+    for (i, combo) in combos.iter().enumerate() {
+        if !matches!(combo.path.try_exists(), Ok(true)) {
+            println!("entry {}: replay not found: {}", i, combo.path.display());
+            problems += 1;
+        }
+        if combo.start > combo.end {
+            println!("entry {}: ends before it starts", i);
+            problems += 1;
+        }
+    }
+
+    println!("{} entries, {} problems", combos.len(), problems);
+    if problems != 0 { std::process::exit(1); }
+}
+
+fn stats(args: &[String]) {
+    let mut verbose = false;
+    let mut playlist = None;
+
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                help(Some("stats"));
+                std::process::exit(0);
+            }
+            "-v" | "--verbose" => verbose = true,
+            f if f.starts_with('-') && f.len() > 1 => unknown_option(f, STATS_USAGE),
+            p if playlist.is_none() => playlist = Some(p),
+            _ => {
+                eprintln!("{}", STATS_USAGE);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let playlist = match playlist {
+        Some(p) => Path::new(p),
+        None => {
+            eprintln!("{}", STATS_USAGE);
+            std::process::exit(1);
+        }
+    };
+
+    let stats = slp_combo_finder::playlist_stats(&read_playlist(playlist));
+
+    if verbose {
+        for (path, count) in stats.per_file.iter() { println!("{:>5}  {}", count, path.display()); }
+    }
+
+    let seconds = stats.total_duration().as_secs();
+    println!("{} entries from {} replays", stats.entries, stats.per_file.len());
+    println!("total clip time {}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60);
+    println!("{} entries with missing replays", stats.missing_entries);
+}
+
+fn serve(args: &[String]) {
+    let mut address = "127.0.0.1:8080".to_string();
+
+    let mut args = args.iter().cloned();
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = split_flag(&arg);
+
+        match flag.as_str() {
+            "-h" | "--help" => {
+                help(Some("serve"));
+                std::process::exit(0);
+            }
+            "--addr" => address = flag_value(&flag, inline_value, &mut args),
+            f => unknown_option(f, SERVE_USAGE),
+        }
+    }
+
+    #[cfg(feature = "server")]
+    server::serve(&address);
+
+    #[cfg(not(feature = "server"))]
+    {
+        eprintln!("Error: cannot serve {}, combo_finder was built without the 'server' feature", address);
+        std::process::exit(1);
+    }
+}
+
+/// Reads and parses a playlist, exiting with an error message on failure.
+fn read_playlist(path: &Path) -> Vec<Combo> {
+    let file = match std::fs::read_to_string(path) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error: could not read '{}': {}", path.display(), e);
+            std::process::exit(1);
+        }
     };
 
-    let config = slp_combo_finder::Config {
-        lead_in: 30,
-        lead_out: 0,
-        strictness,
+    match slp_combo_finder::parse_playlist_json(&file) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error: '{}': {}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Returns the value of a flag, given either inline as '--flag=value' or as the next argument.
+fn flag_value(flag: &str, inline_value: Option<String>, args: &mut impl Iterator<Item=String>) -> String {
+    match inline_value.or_else(|| args.next()) {
+        Some(v) => v,
+        None => {
+            eprintln!("Error: missing value for '{}'", flag);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn non_negative(flag: &str, value: &str) -> f32 {
+    match value.parse::<f32>() {
+        Ok(n) if n.is_finite() && n >= 0.0 => n,
+        _ => {
+            eprintln!("Error: invalid value '{}' for '{}', expected a non-negative number", value, flag);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn count(flag: &str, value: &str) -> usize {
+    match value.parse::<usize>() {
+        Ok(n) => n,
+        Err(_) => {
+            eprintln!("Error: invalid value '{}' for '{}', expected a non-negative integer", value, flag);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn positive(flag: &str, value: &str) -> usize {
+    match value.parse::<usize>() {
+        Ok(n) if n >= 1 => n,
+        _ => {
+            eprintln!("Error: invalid value '{}' for '{}', expected at least 1", value, flag);
+            std::process::exit(1);
+        }
+    }
+}
 
-        player_character: None,
-        player_code: None,
-        player_name: None,
-        opponent_character: None,
-        opponent_code: None,
-        opponent_name: None,
+/// Parses a frame count, either as plain frames ("90") or seconds ("1.5s") at 60fps.
+fn frames(flag: &str, value: &str) -> usize {
+    let parsed = match value.strip_suffix('s') {
+        Some(seconds) => seconds.parse::<f32>().ok()
+            .filter(|s| s.is_finite() && *s >= 0.0)
+            .map(|s| (s * 60.0).round() as usize),
+        None => value.parse::<usize>().ok(),
     };
 
-    let combos = slp_combo_finder::target_path(&config, Path::new(&input_path), None).unwrap(); 
-    slp_combo_finder::write_playlist(combos.as_slice(), Path::new(&out_json_path)).unwrap()
+    match parsed {
+        Some(n) => {
+            if n > LEAD_WARNING_FRAMES {
+                eprintln!("Warning: {} of {} frames ({:.0}s) is unusually large", flag, n, n as f32 / 60.0);
+            }
+            n
+        }
+        None => {
+            eprintln!("Error: invalid value '{}' for '{}', expected a non-negative frame count (90) or seconds (1.5s)", value, flag);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn characters(names: &str) -> Vec<slp_parser::Character> {
+    match slp_combo_finder::parse_characters(names) {
+        Some(c) if !c.is_empty() => c,
+        _ => {
+            eprintln!("Error: unknown character in '{}'", names);
+            eprintln!("Valid characters: {}", slp_combo_finder::CHARACTER_NAMES.join(", "));
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_combo(combo: &Combo) {
+    let file_name = combo.path.file_name().unwrap_or(combo.path.as_os_str()).to_string_lossy();
+    let duration = combo.end.0.saturating_sub(combo.start.0) as f32 / 60.0;
+    let mut line = format!("{}  {} - {}  ({:.1}s)", file_name, timestamp(combo.start.0), timestamp(combo.end.0), duration);
+    if let Some(ref m) = combo.metadata {
+        line.push_str(&format!(
+            "  {:?}{} > {:?}{}  {:.0}%",
+            m.attacker_character, player_tag(&m.attacker_name),
+            m.defender_character, player_tag(&m.defender_name),
+            m.damage(),
+        ));
+        if let Some(tech) = m.tech { line.push_str(&format!("  [{}]", tech.name())); }
+    }
+    println!("{}", line);
+}
+
+fn player_tag(name: &str) -> String {
+    if name.is_empty() { String::new() } else { format!(" ({})", name) }
+}
+
+/// Converts a frame index to mm:ss from the start of the replay.
+fn timestamp(frame: usize) -> String {
+    let seconds = frame / 60;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
 }
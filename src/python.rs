@@ -0,0 +1,123 @@
+//! Python bindings, enabled with the `python` feature. Built with maturin, see `pyproject.toml`.
+
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyFileNotFoundError, PyValueError};
+
+#[pyclass(name = "Config")]
+#[derive(Clone)]
+pub struct PyConfig {
+    inner: crate::Config,
+}
+
+#[pymethods]
+impl PyConfig {
+    /// Arguments left as None take their value from `Config::DEFAULT`.
+    #[new]
+    #[pyo3(signature = (
+        strictness=None, lead_in=None, lead_out=None,
+        player_character=None, player_code=None, player_name=None,
+        opponent_character=None, opponent_code=None, opponent_name=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        strictness: Option<f32>,
+        lead_in: Option<usize>,
+        lead_out: Option<usize>,
+        player_character: Option<&str>,
+        player_code: Option<String>,
+        player_name: Option<String>,
+        opponent_character: Option<&str>,
+        opponent_code: Option<String>,
+        opponent_name: Option<String>,
+    ) -> PyResult<Self> {
+        fn character(name: Option<&str>) -> PyResult<Option<slp_parser::Character>> {
+            name.map(|n| crate::parse_character(n).ok_or_else(|| PyValueError::new_err(format!(
+                "unknown character '{}', expected one of: {}", n, crate::CHARACTER_NAMES.join(", ")
+            )))).transpose()
+        }
+
+        let mut inner = crate::Config::DEFAULT;
+
+        if let Some(s) = strictness {
+            if !(0.0..=1.0).contains(&s) { return Err(PyValueError::new_err("strictness must be between 0 and 1")) }
+            inner.strictness = s;
+        }
+        if let Some(n) = lead_in { inner.lead_in = n; }
+        if let Some(n) = lead_out { inner.lead_out = n; }
+
+        inner.player_character = character(player_character)?;
+        inner.player_code = player_code;
+        inner.player_name = player_name;
+        inner.opponent_character = character(opponent_character)?;
+        inner.opponent_code = opponent_code;
+        inner.opponent_name = opponent_name;
+
+        Ok(PyConfig { inner })
+    }
+}
+
+#[pyclass(name = "Combo")]
+#[derive(Clone)]
+pub struct PyCombo {
+    #[pyo3(get)]
+    pub path: String,
+    /// Frame index, not Slippi frame number.
+    #[pyo3(get)]
+    pub start: usize,
+    #[pyo3(get)]
+    pub end: usize,
+    #[pyo3(get)]
+    pub game_ending: bool,
+}
+
+impl From<&crate::Combo> for PyCombo {
+    fn from(c: &crate::Combo) -> Self {
+        PyCombo {
+            path: c.path.to_string_lossy().into_owned(),
+            start: c.start.0,
+            end: c.end.0,
+            game_ending: c.game_ending,
+        }
+    }
+}
+
+impl From<&PyCombo> for crate::Combo {
+    fn from(c: &PyCombo) -> Self {
+        crate::Combo {
+            path: c.path.clone().into(),
+            start: crate::FrameIndex(c.start),
+            end: crate::FrameIndex(c.end),
+            game_ending: c.game_ending,
+            metadata: None,
+        }
+    }
+}
+
+/// Finds combos in every replay under `path`. The GIL is released during the scan.
+#[pyfunction]
+fn find_combos(py: Python<'_>, config: PyConfig, path: &str) -> PyResult<Vec<PyCombo>> {
+    let combos = py.allow_threads(|| crate::target_path(&config.inner, std::path::Path::new(path), None));
+
+    match combos {
+        Ok(combos) => Ok(combos.iter().map(PyCombo::from).collect()),
+        Err(crate::TargetPathError::PathNotFound) => Err(PyFileNotFoundError::new_err(path.to_string())),
+        Err(e) => Err(PyValueError::new_err(format!("{} ({})", e, path))),
+    }
+}
+
+#[pyfunction]
+#[pyo3(name = "write_playlist")]
+fn write_playlist(combos: Vec<PyCombo>, path: &str) -> PyResult<()> {
+    let combos = combos.iter().map(crate::Combo::from).collect::<Vec<_>>();
+    crate::write_playlist(&combos, std::path::Path::new(path))?;
+    Ok(())
+}
+
+#[pymodule]
+fn slp_combo_finder(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyConfig>()?;
+    m.add_class::<PyCombo>()?;
+    m.add_function(wrap_pyfunction!(find_combos, m)?)?;
+    m.add_function(wrap_pyfunction!(write_playlist, m)?)?;
+    Ok(())
+}
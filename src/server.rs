@@ -0,0 +1,74 @@
+//! `combo_finder serve`, enabled with the `server` feature.
+//!
+//! `GET /health` returns `{"status":"ok"}`.
+//! `POST /search` takes a json object of `Config` fields plus a `path` to scan, and returns the playlist json.
+//! The path is a 404 if it does not exist, a 400 if it is a file that is not a replay, and a 422 if it has no replays.
+//! Each request is handled on its own thread, so a long search doesn't block health checks.
+
+use std::io::Read;
+use std::path::Path;
+use tiny_http::{Header, Method, Request, Response};
+use slp_combo_finder::TargetPathError;
+
+pub fn serve(address: &str) {
+    let server = match tiny_http::Server::http(address) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Error: could not listen on {}: {}", address, e);
+            std::process::exit(1);
+        }
+    };
+    eprintln!("Listening on http://{}", address);
+
+    for request in server.incoming_requests() {
+        std::thread::spawn(move || handle(request));
+    }
+}
+
+fn handle(mut request: Request) {
+    let (status, body) = match (request.method(), request.url()) {
+        (&Method::Get, "/health") => (200, json::object!{ status: "ok" }.dump()),
+        (&Method::Post, "/search") => search(&mut request),
+        _ => (404, error_json("not found")),
+    };
+
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type);
+    let _ = request.respond(response);
+}
+
+fn search(request: &mut Request) -> (u16, String) {
+    let mut body = String::new();
+    if let Err(e) = request.as_reader().read_to_string(&mut body) {
+        return (400, error_json(&format!("could not read body: {}", e)));
+    }
+
+    let body = match json::parse(&body) {
+        Ok(b) => b,
+        Err(e) => return (400, error_json(&format!("invalid json: {}", e))),
+    };
+
+    let config = match slp_combo_finder::Config::from_json_value(&body) {
+        Ok(c) => c,
+        Err(e) => return (400, error_json(&e.to_string())),
+    };
+
+    let path = match body["path"].as_str() {
+        Some(p) => p,
+        None => return (400, error_json("missing 'path'")),
+    };
+
+    match slp_combo_finder::target_path(&config, Path::new(path), None) {
+        Ok(combos) => (200, slp_combo_finder::write_playlist_to_string(&combos)),
+        Err(e @ TargetPathError::PathNotFound) => (404, error_json(&e.to_string())),
+        Err(e @ TargetPathError::NotAReplay) => (400, error_json(&e.to_string())),
+        // the path is fine, there is just nothing in it to scan
+        Err(e @ (TargetPathError::NoMatches | TargetPathError::NoFilesFound)) => (422, error_json(&e.to_string())),
+    }
+}
+
+fn error_json(message: &str) -> String {
+    json::object!{ error: message }.dump()
+}
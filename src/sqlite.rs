@@ -0,0 +1,83 @@
+//! SQLite output, enabled with the `sqlite` feature.
+//!
+//! Frames are stored with the same numbering as playlists.
+//! `game_timestamp` is not read from replays yet and is written as NULL.
+//! The metadata columns are NULL for combos without metadata, e.g. ones read from a playlist.
+
+use std::path::{Path, PathBuf};
+use crate::{Combo, FrameIndex};
+
+/// Inserts each combo as a row of the `combos` table, creating it if needed.
+/// Existing rows are kept, so writing to the same database twice appends.
+pub fn write_playlist_sqlite(combos: &[Combo], db_path: &Path) -> rusqlite::Result<()> {
+    let mut conn = rusqlite::Connection::open(db_path)?;
+    let tx = conn.transaction()?;
+
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS combos (
+            id INTEGER PRIMARY KEY,
+            path TEXT NOT NULL,
+            start_frame INTEGER NOT NULL,
+            end_frame INTEGER NOT NULL,
+            attacker_character TEXT,
+            defender_character TEXT,
+            start_percent REAL,
+            end_percent REAL,
+            hit_count INTEGER,
+            game_timestamp TEXT
+        )",
+        [],
+    )?;
+
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO combos (
+                path, start_frame, end_frame,
+                attacker_character, defender_character, start_percent, end_percent,
+                hit_count, game_timestamp
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
+        )?;
+
+        for c in combos {
+            let m = c.metadata.as_ref();
+            insert.execute(rusqlite::params![
+                c.path.to_string_lossy().into_owned(),
+                c.start.to_slippi_frame(),
+                c.end.to_slippi_frame(),
+                m.map(|m| format!("{:?}", m.attacker_character)),
+                m.map(|m| format!("{:?}", m.defender_character)),
+                m.map(|m| m.start_percent as f64),
+                m.map(|m| m.end_percent as f64),
+                m.map(|m| m.hit_count as i64),
+                None::<String>,
+            ])?;
+        }
+    }
+
+    tx.commit()
+}
+
+/// Reads back the path and frame range of every row, in insertion order.
+/// Metadata is not restored, as the table does not store all of it.
+/// A frame before `FRAME_OFFSET` is an `IntegralValueOutOfRange` error.
+pub fn read_playlist_sqlite(db_path: &Path) -> rusqlite::Result<Vec<Combo>> {
+    let conn = rusqlite::Connection::open(db_path)?;
+    let mut query = conn.prepare("SELECT path, start_frame, end_frame FROM combos ORDER BY id")?;
+
+    let combos = query.query_map([], |row| {
+        // frames before `FRAME_OFFSET` are rejected rather than clamped
+        let frame = |col| -> rusqlite::Result<FrameIndex> {
+            let n = row.get(col)?;
+            FrameIndex::try_from_slippi_frame(n).ok_or(rusqlite::Error::IntegralValueOutOfRange(col, n))
+        };
+        Ok(Combo {
+            path: PathBuf::from(row.get::<_, String>(0)?),
+            start: frame(1)?,
+            end: frame(2)?,
+            game_ending: false,
+            metadata: None,
+        })
+    })?.collect::<rusqlite::Result<Vec<_>>>();
+
+    combos
+}
@@ -0,0 +1,45 @@
+//! Javascript bindings, enabled with the `wasm` feature.
+//!
+//! Replays are read with `std::fs`, so this requires a wasm runtime with filesystem access (e.g. WASI).
+
+use std::path::PathBuf;
+use wasm_bindgen::prelude::*;
+
+/// `config_json` is a json object as accepted by `Config::from_json`, `paths` is an array of replay paths.
+///
+/// Returns an array of `{ path, startFrame, endFrame }` objects, using the same frame numbering as playlists.
+/// Throws if the config is invalid, `paths` holds anything but strings, or a path cannot be scanned,
+/// e.g. it does not exist. No matching combos is an empty array.
+#[wasm_bindgen]
+pub fn find_combos_wasm(config_json: &str, paths: js_sys::Array) -> js_sys::Array {
+    let config = match crate::Config::from_json(config_json) {
+        Ok(c) => c,
+        Err(e) => wasm_bindgen::throw_str(&e.to_string()),
+    };
+
+    let targets = paths.iter()
+        .enumerate()
+        .map(|(i, p)| match p.as_string() {
+            Some(p) => PathBuf::from(p),
+            None => wasm_bindgen::throw_str(&format!("paths[{}] is not a string", i)),
+        })
+        .collect::<Vec<_>>();
+
+    let combos = match crate::target_paths(&config, &targets, None) {
+        Ok(c) => c,
+        Err(e) => wasm_bindgen::throw_str(&e.to_string()),
+    };
+
+    combos.iter()
+        .map(|c| {
+            let obj = js_sys::Object::new();
+            let path = JsValue::from_str(&c.path.to_string_lossy());
+            let start = JsValue::from_f64(c.start.to_slippi_frame() as f64);
+            let end = JsValue::from_f64(c.end.to_slippi_frame() as f64);
+            js_sys::Reflect::set(&obj, &JsValue::from_str("path"), &path).unwrap();
+            js_sys::Reflect::set(&obj, &JsValue::from_str("startFrame"), &start).unwrap();
+            js_sys::Reflect::set(&obj, &JsValue::from_str("endFrame"), &end).unwrap();
+            obj
+        })
+        .collect()
+}
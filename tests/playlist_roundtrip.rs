@@ -0,0 +1,31 @@
+use proptest::prelude::*;
+use slp_combo_finder::{Combo, FrameIndex, ParsePlaylistError, FRAME_OFFSET};
+
+proptest! {
+    // Any list of valid combos written as a playlist must parse back to the same paths and frames,
+    // so `FRAME_OFFSET` cancels out.
+    #[test]
+    fn written_playlists_parse_back(entries in prop::collection::vec((".*", 0..1_000_000usize, 0..1_000_000usize), 0..32)) {
+        let combos = entries.into_iter()
+            .map(|(path, a, b)| Combo::new(path, a.min(b), a.max(b)))
+            .collect::<Vec<_>>();
+
+        let playlist = slp_combo_finder::write_playlist_to_string(&combos);
+        let parsed = slp_combo_finder::parse_playlist_json(&playlist).expect("written playlist failed to parse");
+
+        let frames = |c: &[Combo]| c.iter().map(|c| (c.path.clone(), c.start, c.end)).collect::<Vec<_>>();
+        prop_assert_eq!(frames(&combos), frames(&parsed));
+    }
+
+    // Frames before the first stored frame are rejected, not clamped to it.
+    #[test]
+    fn frames_before_the_offset_are_rejected(frame in -1_000_000_000i64..FRAME_OFFSET as i64) {
+        prop_assert_eq!(FrameIndex::try_from_slippi_frame(frame), None);
+        prop_assert!(Combo::from_slippi_frames("a.slp", frame, 0).is_none());
+        prop_assert!(Combo::from_slippi_frames("a.slp", 0, frame).is_none());
+
+        let playlist = format!(r#"{{"mode":"queue","queue":[{{"path":"a.slp","startFrame":{},"endFrame":0}}]}}"#, frame);
+        let parsed = slp_combo_finder::parse_playlist_json(&playlist);
+        prop_assert!(matches!(parsed, Err(ParsePlaylistError::FrameOutOfRange { entry: 0, frame: f }) if f == frame));
+    }
+}